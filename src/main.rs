@@ -1,6 +1,17 @@
 
+// Bevy systems take a lot of parameters, and this file spells out struct fields and nests its ifs on purpose
+#![allow(
+	clippy::too_many_arguments,
+	clippy::type_complexity,
+	clippy::collapsible_if,
+	clippy::redundant_field_names,
+	clippy::init_numbered_fields,
+	clippy::extra_unused_lifetimes,
+	clippy::wrong_self_convention,
+)]
+
 use std::io::prelude::*;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::ops::Add;
 
 use bevy:: {
@@ -8,12 +19,16 @@ use bevy:: {
 	dev_tools::fps_overlay::{FpsOverlayConfig, FpsOverlayPlugin, FrameTimeGraphConfig},
 	text::FontSmoothing,
 	math::*,
+	ecs::system::SystemId,
+	audio::{SpatialScale, Volume},
+	input::{ButtonState, InputSystems, keyboard::{Key, KeyboardInput}},
 };
 use bevy_framepace::*;
 
 #[allow(unused)]
 const BACKGROUND_LAYER: f32 = 0.0;
 const BLOCK_LAYER: f32 = 1.0;
+const OVERLAY_LAYER: f32 = 1.5;
 const MOB_LAYER: f32 = 2.0;
 
 type TileId = usize;
@@ -44,16 +59,31 @@ fn main() {
 		.add_plugins(FramepacePlugin)
 
 		.insert_resource(ClearColor(Color::srgb(0.3, 0.6, 0.6)))
-		.insert_resource(TilesShouldUpdate{ should_update: true })
+		.insert_resource(Time::<Fixed>::from_hz(PHYSICS_TICK_RATE))
+		.insert_resource(PhysicsConfig::load().unwrap_or_default())
 		.insert_resource(TileChangeQueue {..default()})
+		.insert_resource(DirtyTiles {..default()})
+		.insert_resource(EditHistory {..default()})
+		.insert_resource(Clipboard {..default()})
+		.insert_resource(SoundSettings {..default()})
+		.insert_resource(FluidLevels {..default()})
+		.insert_resource(TickScheduler::new(WORLD_SEED))
+		.insert_resource(GrassSettings::load().unwrap_or_default())
+		.insert_resource(LightMap {..default()})
+		.insert_resource(Pathfinder {..default()})
+		.insert_resource(WorldTime::load().unwrap_or_default())
+		.add_message::<TileChanged>()
+		.add_message::<PlacementRejected>()
 
 		.add_systems(Startup, (setup, init_chunks).chain())
-		.add_systems(PreUpdate, (change_tile_sprites, update_tiles).run_if(run_if_tiles_should_update))
-		.add_systems(Update, (fps_update_config, player_input, do_physics, walk_animation, update_camera, debug_input))
+		.add_systems(PreUpdate, schematic_name_prompt.after(InputSystems))
+		.add_systems(PreUpdate, (mark_new_chunks, change_tile_sprites.run_if(run_if_tiles_changed), update_tiles.run_if(run_if_tiles_dirty), run_tile_hooks, wake_fluids, update_lighting, tint_tiles).chain())
+		.add_systems(FixedUpdate, (spawn_creatures, creature_ai, do_physics, push_mobs, land_falling_tiles, flow_fluids, run_ticks, advance_world_time).chain())
+		.add_systems(Update, (fps_update_config, player_input, break_tiles, record_history, history_input, clipboard_input, play_tile_sounds, play_footsteps, spawn_tile_particles, update_particles, show_placement_rejections, apply_physics_config, physics_panel, walk_animation, update_camera, update_mob_sprites, update_sky, world_time_input, debug_input, find_paths))
 	.run();
 }
 
-#[derive(Copy, Clone, PartialEq)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
 struct ChunkPosition(I64Vec2);
 
 impl ChunkPosition {
@@ -75,7 +105,7 @@ impl ChunkRelativePosition {
 	fn is_in_chunk(&self) -> bool { self.0.x < Chunk::WIDTH_I64 && self.0.y < Chunk::HEIGHT_I64 }
 }
 
-#[derive(Copy, Clone, PartialEq)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
 struct TileAbsolutePosition(I64Vec2);
 
 impl TileAbsolutePosition {
@@ -108,108 +138,373 @@ impl ToTileAbsolutePosition for (ChunkPosition, ChunkRelativePosition) {
 	}
 }
 
-#[derive(Resource)]
-struct TilesShouldUpdate {
-	should_update: bool,
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+enum TileLayer {
+	Background,
+	Foreground,
+}
+
+impl TileLayer {
+	#[inline]
+	fn select(&self, tiles: (Entity, Entity)) -> Entity {
+		match self {
+			TileLayer::Background => tiles.0,
+			TileLayer::Foreground => tiles.1,
+		}
+	}
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum TileChangeCause {
+	Player,
+	Load,
+	World,
+	History,
 }
 
 #[derive(Resource, Default)]
 struct TileChangeQueue {
-	queue: Vec<(TileId, bool, TileAbsolutePosition)>,
+	queue: Vec<(TileId, TileLayer, TileAbsolutePosition, TileChangeCause)>,
 }
 
 impl TileChangeQueue {
 	#[inline]
-	fn push(&mut self, to_push: (TileId, bool, TileAbsolutePosition)) { self.queue.push(to_push); }
+	fn push(&mut self, to_push: (TileId, TileLayer, TileAbsolutePosition, TileChangeCause)) { self.queue.push(to_push); }
 	#[inline]
 	fn clear(&mut self) { self.queue.clear(); }
+
+	fn fill(&mut self, region: TileRegion, id: TileId, layer: TileLayer, cause: TileChangeCause) {
+		for pos in region.iter() {
+			self.push((id, layer, pos, cause));
+		}
+	}
+
+	fn replace(
+		&mut self,
+		chunks: &Query<&Chunk>,
+		tiles: &Query<&Tile>,
+		region: TileRegion,
+		from: TileId,
+		to: TileId,
+		layer: TileLayer,
+		cause: TileChangeCause,
+	) {
+		for pos in region.iter() {
+			if chunks.tile_id_at(tiles, pos, layer) == Some(from) {
+				self.push((to, layer, pos, cause));
+			}
+		}
+	}
+
+	fn line(&mut self, from: TileAbsolutePosition, to: TileAbsolutePosition, id: TileId, layer: TileLayer, cause: TileChangeCause) {
+		let delta_x: i64 = (to.0.x - from.0.x).abs();
+		let delta_y: i64 = -(to.0.y - from.0.y).abs();
+		let step_x: i64 = (to.0.x - from.0.x).signum();
+		let step_y: i64 = (to.0.y - from.0.y).signum();
+		let mut error: i64 = delta_x + delta_y;
+		let mut pos: TileAbsolutePosition = from;
+		loop {
+			self.push((id, layer, pos, cause));
+			if pos == to { break; }
+			if error * 2 >= delta_y {
+				error += delta_y;
+				pos = pos + (step_x, 0);
+			}
+			if error * 2 <= delta_x {
+				error += delta_x;
+				pos = pos + (0, step_y);
+			}
+		}
+	}
+
+	// Nothing is queued if the connected area is bigger than the limit
+	fn flood_fill(
+		&mut self,
+		chunks: &Query<&Chunk>,
+		tiles: &Query<&Tile>,
+		start: TileAbsolutePosition,
+		id: TileId,
+		layer: TileLayer,
+		cause: TileChangeCause,
+		limit: usize,
+	) -> Result<usize, PlacementRejection> {
+		let target: TileId = match chunks.tile_id_at(tiles, start, layer) {
+			Some(found) => found,
+			None => return Err(PlacementRejection::OutsideWorld),
+		};
+		if target == id { return Ok(0); }
+
+		let mut visited: HashSet<TileAbsolutePosition> = HashSet::from([start]);
+		let mut to_visit: VecDeque<TileAbsolutePosition> = VecDeque::from([start]);
+		let mut filling: Vec<TileAbsolutePosition> = Vec::new();
+		while let Some(pos) = to_visit.pop_front() {
+			filling.push(pos);
+			if filling.len() > limit {
+				return Err(PlacementRejection::AreaTooLarge);
+			}
+			for offset in [(0, 1), (0, -1), (1, 0), (-1, 0)] {
+				let next: TileAbsolutePosition = pos + offset;
+				if !visited.contains(&next) && chunks.tile_id_at(tiles, next, layer) == Some(target) {
+					visited.insert(next);
+					to_visit.push_back(next);
+				}
+			}
+		}
+		for pos in filling.iter() {
+			self.push((id, layer, *pos, cause));
+		}
+		Ok(filling.len())
+	}
 }
 
-fn run_if_tiles_should_update(
-	should_update: Res<TilesShouldUpdate>
+#[derive(Copy, Clone, PartialEq, Eq)]
+struct TileRegion {
+	min: TileAbsolutePosition,
+	max: TileAbsolutePosition,
+}
+
+impl TileRegion {
+	#[inline]
+	fn from_corners(a: TileAbsolutePosition, b: TileAbsolutePosition) -> Self {
+		Self {
+			min: TileAbsolutePosition::new(a.0.x.min(b.0.x), a.0.y.min(b.0.y)),
+			max: TileAbsolutePosition::new(a.0.x.max(b.0.x), a.0.y.max(b.0.y)),
+		}
+	}
+
+	#[inline]
+	fn area(&self) -> usize { ((self.max.0.x - self.min.0.x + 1) as usize).saturating_mul((self.max.0.y - self.min.0.y + 1) as usize) }
+
+	#[inline]
+	fn iter(&self) -> impl Iterator<Item = TileAbsolutePosition> + use<> {
+		let (min, max) = (self.min, self.max);
+		(min.0.y..=max.0.y).flat_map(move |y| (min.0.x..=max.0.x).map(move |x| TileAbsolutePosition::new(x, y)))
+	}
+}
+
+#[derive(Resource, Default)]
+struct DirtyTiles {
+	tiles: HashSet<TileAbsolutePosition>,
+}
+
+impl DirtyTiles {
+	// A tile's autotile mask depends on its four neighbours, so they need recomputing too
+	#[inline]
+	fn mark(&mut self, pos: TileAbsolutePosition) {
+		self.tiles.insert(pos);
+		self.tiles.insert(pos + (0, 1));
+		self.tiles.insert(pos + (0, -1));
+		self.tiles.insert(pos + (1, 0));
+		self.tiles.insert(pos + (-1, 0));
+	}
+
+	fn mark_chunk(&mut self, pos: ChunkPosition) {
+		for y in -1..=Chunk::HEIGHT_I64 {
+			for x in -1..=Chunk::WIDTH_I64 {
+				self.tiles.insert((pos, ChunkRelativePosition::new(0, 0)).to_tile_absolute_position() + (x, y));
+			}
+		}
+	}
+}
+
+fn run_if_tiles_changed(
+	to_change: Res<TileChangeQueue>
 ) -> bool {
-	if should_update.should_update {
-		true
-	} else {
-		false
+	!to_change.queue.is_empty()
+}
+
+fn run_if_tiles_dirty(
+	dirty: Res<DirtyTiles>
+) -> bool {
+	!dirty.tiles.is_empty()
+}
+
+fn mark_new_chunks(
+	mut dirty: ResMut<DirtyTiles>,
+	chunks: Query<&Chunk, Added<Chunk>>,
+) {
+	for chunk in &chunks {
+		dirty.mark_chunk(chunk.pos);
 	}
 }
 
+#[derive(Message, Copy, Clone)]
+struct TileChanged {
+	pos: TileAbsolutePosition,
+	layer: TileLayer,
+	old: TileId,
+	new: TileId,
+	cause: TileChangeCause,
+}
+
 fn change_tile_sprites(
 	mut to_change: ResMut<TileChangeQueue>,
+	mut dirty: ResMut<DirtyTiles>,
+	mut changed: MessageWriter<TileChanged>,
 	tile_ids: Res<TileIds>,
 	mut tiles: Query<(&mut Tile, &mut Sprite)>,
 	chunks: Query<&Chunk>,
 ) {
-	for (change_to, _, pos) in &to_change.queue {
+	for (change_to, layer, pos, cause) in &to_change.queue {
 		if let Some(entity) = chunks.tile_at(*pos) {
-		if let Ok((mut tile, mut sprite)) = tiles.get_mut(entity.1) {
+		if let Ok((mut tile, mut sprite)) = tiles.get_mut(layer.select(entity)) {
+			if tile.id == *change_to { continue; }
+			changed.write(TileChanged {
+				pos: *pos,
+				layer: *layer,
+				old: tile.id,
+				new: *change_to,
+				cause: *cause,
+			});
 			tile.id = *change_to;
 			sprite.texture_atlas.as_mut().unwrap().layout = tile_ids.by_id(*change_to).texture.0.clone();
 			sprite.image = tile_ids.by_id(*change_to).texture.1.clone();
 			sprite.texture_atlas.as_mut().unwrap().index = 0;
+			dirty.mark(*pos);
 		}}
 	}
 	to_change.clear();
 }
 
+#[derive(Resource, Default)]
+struct EditHistory {
+	undo: VecDeque<Vec<TileChanged>>,
+	redo: Vec<Vec<TileChanged>>,
+	pending: Vec<TileChanged>,
+	grouping: bool,
+}
+
+impl EditHistory {
+	const MAX_STEPS: usize = 128;
+
+	// Everything recorded between begin_group and end_group becomes a single undo step
+	#[inline]
+	fn begin_group(&mut self) { self.grouping = true; }
+	#[inline]
+	fn end_group(&mut self) { self.grouping = false; }
+
+	fn commit(&mut self) {
+		if self.pending.is_empty() { return; }
+		self.undo.push_back(std::mem::take(&mut self.pending));
+		if self.undo.len() > Self::MAX_STEPS {
+			self.undo.pop_front();
+		}
+		self.redo.clear();
+	}
+
+	fn undo(&mut self, queue: &mut TileChangeQueue) {
+		if let Some(step) = self.undo.pop_back() {
+			for change in step.iter().rev() {
+				queue.push((change.old, change.layer, change.pos, TileChangeCause::History));
+			}
+			self.redo.push(step);
+		}
+	}
+
+	fn redo(&mut self, queue: &mut TileChangeQueue) {
+		if let Some(step) = self.redo.pop() {
+			for change in step.iter() {
+				queue.push((change.new, change.layer, change.pos, TileChangeCause::History));
+			}
+			self.undo.push_back(step);
+		}
+	}
+}
+
+fn record_history(
+	mut changed: MessageReader<TileChanged>,
+	mut history: ResMut<EditHistory>,
+) {
+	for change in changed.read() {
+		if change.cause == TileChangeCause::Player {
+			history.pending.push(*change);
+		}
+	}
+	if !history.grouping {
+		history.commit();
+	}
+}
+
+fn history_input(
+	keys: Res<ButtonInput<KeyCode>>,
+	mut history: ResMut<EditHistory>,
+	mut update_queue: ResMut<TileChangeQueue>,
+) {
+	if keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+		let shift: bool = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+		if keys.just_pressed(KeyCode::KeyZ) && !shift {
+			history.undo(&mut update_queue);
+		} else if keys.just_pressed(KeyCode::KeyY) || (keys.just_pressed(KeyCode::KeyZ) && shift) {
+			history.redo(&mut update_queue);
+		}
+	}
+}
+
+fn run_tile_hooks(
+	mut commands: Commands,
+	mut changed: MessageReader<TileChanged>,
+	tile_ids: Res<TileIds>,
+	chunks: Query<&Chunk>,
+	tiles: Query<&Tile>,
+) {
+	for change in changed.read() {
+		if change.old != TileIds::AIR {
+		if let Some(hook) = tile_ids.by_id(change.old).hooks.on_broken {
+			commands.run_system_with(hook, (change.pos, *change));
+		}}
+		if change.new != TileIds::AIR {
+		if let Some(hook) = tile_ids.by_id(change.new).hooks.on_placed {
+			commands.run_system_with(hook, (change.pos, *change));
+		}}
+		for offset in [(0, 1), (0, -1), (1, 0), (-1, 0)] {
+			let neighbour_pos: TileAbsolutePosition = change.pos + offset;
+			if let Some(entity) = chunks.tile_at(neighbour_pos) {
+			if let Ok(neighbour) = tiles.get(change.layer.select(entity)) {
+			if let Some(hook) = tile_ids.by_tile(neighbour).hooks.on_neighbour_changed {
+				commands.run_system_with(hook, (neighbour_pos, *change));
+			}}}
+		}
+	}
+}
+
 fn update_tiles(
 	tile_ids: Res<TileIds>,
+	fluids: Res<FluidLevels>,
 	chunks: Query<&Chunk>,
 	check: Query<&Tile>,
-	mut should_update: ResMut<TilesShouldUpdate>,
-	mut tiles: Query<(Entity, &mut Sprite, &mut Transform), With<Tile>>,
+	mut dirty: ResMut<DirtyTiles>,
+	mut tiles: Query<(&Tile, &mut Sprite, &mut Transform)>,
 ) {
-	should_update.should_update = false;
-	for (entity, mut sprite, mut transfem) in &mut tiles {
-//		if !tile_ids.by_tile(block).smooths { continue; }
-
-		if let Some(current_pos) = chunks.find_tile(entity) {
-			sprite.texture_atlas.as_mut().unwrap().index =
-				match chunks.tile_at(current_pos + (0, 1)) {
-					Some(checking) =>
-					match check.get(checking.1) {
-						Ok(tile) => match tile_ids.by_tile(tile).smooths {
-							true => 1,
-							false => 0,
-						},
-						_ => 0,
-					},  _ => 0,
-				} |
-				match chunks.tile_at(current_pos + (0, -1)) {
-					Some(checking) =>
-					match check.get(checking.1) {
-						Ok(tile) => match tile_ids.by_tile(tile).smooths {
-							true => 2,
-							false => 0,
-						},
-						_ => 0,
-					},  _ => 0,
-				} |
-				match chunks.tile_at(current_pos + (1, 0)) {
-					Some(checking) =>
-					match check.get(checking.1) {
-						Ok(tile) => match tile_ids.by_tile(tile).smooths {
-							true => 4,
-							false => 0,
-						},
-						_ => 0,
-					},  _ => 0,
-				} |
-				match chunks.tile_at(current_pos + (-1, 0)) {
+	for current_pos in dirty.tiles.drain() {
+		if let Some(entities) = chunks.tile_at(current_pos) {
+		for entity in [entities.0, entities.1] {
+		if let Ok((current, mut sprite, mut transfem)) = tiles.get_mut(entity) {
+			let current_data: &TileData = tile_ids.by_tile(current);
+			let connects = |offset: (i64, i64), bit: usize| -> usize {
+				match chunks.tile_at(current_pos + offset) {
 					Some(checking) =>
 					match check.get(checking.1) {
-						Ok(tile) => match tile_ids.by_tile(tile).smooths {
-							true => 8,
+						Ok(tile) => match current_data.connects_to(tile_ids.by_tile(tile)) {
+							true => bit,
 							false => 0,
 						},
 						_ => 0,
 					},  _ => 0,
-				};
+				}
+			};
+
+			sprite.texture_atlas.as_mut().unwrap().index = match current_data.fluid {
+				Some(_) => fluids.level(current_pos).max(1) as usize - 1,
+				None =>
+					connects((0, 1), 1) |
+					connects((0, -1), 2) |
+					connects((1, 0), 4) |
+					connects((-1, 0), 8),
+			};
 
 			transfem.translation.x = current_pos.0.x as f32 * 8.0;
 			transfem.translation.y = current_pos.0.y as f32 * 8.0;
-		}
+		}}}
 	}
 }
 
@@ -229,7 +524,16 @@ impl TileIds {
 	const STONEBRICK: TileId = 6;
 	const GLASS: TileId = 7;
 	const GLASSPANE: TileId = 8;
-	const BLOCKS: TileId = 9;
+	const PLATFORM: TileId = 9;
+	const LADDER: TileId = 10;
+	const WATER: TileId = 11;
+	const SAND: TileId = 12;
+	const LAMP: TileId = 13;
+	const STONESLAB: TileId = 14;
+	const STONESLABTOP: TileId = 15;
+	const STONESLOPERIGHT: TileId = 16;
+	const STONESLOPELEFT: TileId = 17;
+	const BLOCKS: TileId = 18;
 
 	#[inline]
 	fn new(tiles: [TileData; Self::BLOCKS]) -> Self { Self { tiles: tiles } }
@@ -276,14 +580,33 @@ enum WalkState {
 	TryRight = 1,
 }
 
+#[derive(Copy, Clone)]
+enum ClimbDirection {
+	TryDown = -1,
+	None = 0,
+	TryUp = 1,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum ClimbState {
+	None,
+	Climbing,
+}
+
 #[derive(Component)]
 struct Mob {
 	position: Vec2,
+	previous_position: Vec2,
 	velocity: Vec2,
 	size: Vec2,
 	touching_grass: bool,
 	jump_state: JumpState,
 	walk_state: WalkState,
+	climb_direction: ClimbDirection,
+	climb_state: ClimbState,
+	pushes_mobs: bool,
+	drop_through: bool,
+	contacts: Contacts,
 }
 
 impl<'a> Default for Mob {
@@ -291,34 +614,86 @@ impl<'a> Default for Mob {
 	fn default() -> Self {
 		Self {
 			position: Vec2::new(0.0, 140.0),
+			previous_position: Vec2::new(0.0, 140.0),
 			velocity: Vec2::new(0.0, 0.0),
 			size: Vec2::new(1.0, 3.0),
 			touching_grass: false,
 			jump_state: JumpState::None,
 			walk_state: WalkState::None,
+			climb_direction: ClimbDirection::None,
+			climb_state: ClimbState::None,
+			pushes_mobs: true,
+			drop_through: false,
+			contacts: Contacts::default(),
 		}
 	}
 }
 
+impl Mob {
+	// Physics runs on a fixed tick, so rendering blends between the last two ticks
+	#[inline]
+	fn interpolated_position(&self, alpha: f32) -> Vec2 {
+		self.previous_position.lerp(self.position, alpha)
+	}
+
+	#[inline]
+	fn center(&self) -> Vec2 { self.position + self.size * 0.5 }
+
+	// The tile its feet are in, which is what paths are made of
+	#[inline]
+	fn standing_at(&self) -> TileAbsolutePosition {
+		TileAbsolutePosition::new((self.position.x + self.size.x * 0.5).floor() as i64, self.position.y.round() as i64)
+	}
+
+	// Matches the cells sweep_aabb treats the mob as overlapping
+	#[inline]
+	fn occupies(&self, pos: TileAbsolutePosition) -> bool {
+		pos.0.x >= self.position.x.floor() as i64 && pos.0.x < (self.position.x + self.size.x).ceil() as i64 &&
+		pos.0.y >= self.position.y.floor() as i64 && pos.0.y < (self.position.y + self.size.y).ceil() as i64
+	}
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum EditTool {
+	Single,
+	Line,
+	Rectangle,
+	Replace,
+	Flood,
+	Select,
+}
+
 #[derive(Component)]
 struct Player {
 	selected_block: TileId,
+	tool: EditTool,
+	drag_start: Option<TileAbsolutePosition>,
+	breaking: Option<(TileAbsolutePosition, f32)>,
 }
 
 impl<'a> Default for Player {
 	#[inline]
 	fn default() -> Self {
 		Player {
-			selected_block: TileIds::DIRT
+			selected_block: TileIds::DIRT,
+			tool: EditTool::Single,
+			drag_start: None,
+			breaking: None,
 		}
 	}
 }
 
-#[derive(Component)]
+#[derive(Component, Clone)]
 struct PlayerAnimation {
 	idle_frame: usize,
 	first_walk_frame: usize,
 	last_walk_frame: usize,
+	first_climb_frame: usize,
+	last_climb_frame: usize,
+	// Standing, walking and climbing can each have their own sheet, the sprite swaps between them
+	idle_sheet: (Handle<Image>, Handle<TextureAtlasLayout>),
+	walk_sheet: (Handle<Image>, Handle<TextureAtlasLayout>),
+	climb_sheet: (Handle<Image>, Handle<TextureAtlasLayout>),
 }
 
 impl<'a> Default for PlayerAnimation {
@@ -327,7 +702,12 @@ impl<'a> Default for PlayerAnimation {
 		Self {
 			idle_frame: 0,
 			first_walk_frame: 1,
-			last_walk_frame: 7
+			last_walk_frame: 7,
+			first_climb_frame: 0,
+			last_climb_frame: 1,
+			idle_sheet: default(),
+			walk_sheet: default(),
+			climb_sheet: default(),
 		}
 	}
 }
@@ -340,29 +720,154 @@ struct Tile {
 	id: TileId,
 }
 
+struct SmoothGroups;
+
+impl SmoothGroups {
+	const NONE: u32 = 0;
+	const EARTH: u32 = 1 << 0;
+	const WOOD: u32 = 1 << 1;
+	const STONE: u32 = 1 << 2;
+	const GLASS: u32 = 1 << 3;
+}
+
+// Hooks receive the position of the tile they belong to, alongside the change that triggered them
+type TileHook = SystemId<In<(TileAbsolutePosition, TileChanged)>>;
+type TileTickHook = SystemId<In<(TileAbsolutePosition, TileLayer)>>;
+
+#[derive(Default)]
+struct TileHooks {
+	on_placed: Option<TileHook>,
+	on_broken: Option<TileHook>,
+	on_neighbour_changed: Option<TileHook>,
+	on_random_tick: Option<TileTickHook>,
+	on_scheduled_tick: Option<TileTickHook>,
+}
+
 #[derive(Component)]
 struct TileData {
 	smooths: bool,
+	smooth_groups: u32,
 	solid: bool,
 	texture: (Handle<TextureAtlasLayout>, Handle<Image>),
+	hooks: TileHooks,
+	placement: PlacementRule,
+	hardness: f32,
+	sounds: TileSounds,
+	friction: f32,
+	bounciness: f32,
+	shape: CollisionShape,
+	climbable: bool,
+	fluid: Option<FluidData>,
+	falls: bool,
+	light_opacity: u8,
+	light_emission: u8,
 }
 
 impl TileData {
 	#[inline]
-	fn new(smooths: bool, solid: bool, texture: (Handle<TextureAtlasLayout>, Handle<Image>)) -> Self {
+	fn new(smooths: bool, smooth_groups: u32, solid: bool, texture: (Handle<TextureAtlasLayout>, Handle<Image>)) -> Self {
 		Self {
 			smooths: smooths,
+			smooth_groups: smooth_groups,
 			solid: solid,
 			texture: texture,
+			hooks: TileHooks::default(),
+			placement: PlacementRule::Adjacent,
+			hardness: 0.5,
+			sounds: TileSounds::default(),
+			friction: 1.0,
+			bounciness: 0.0,
+			shape: match solid {
+				true => CollisionShape::Full,
+				false => CollisionShape::None,
+			},
+			climbable: false,
+			fluid: None,
+			falls: false,
+			light_opacity: match solid {
+				true => 4,
+				false => 0,
+			},
+			light_emission: 0,
 		}
 	}
+
+	#[inline]
+	fn with_hooks(mut self, hooks: TileHooks) -> Self {
+		self.hooks = hooks;
+		self
+	}
+
+	#[inline]
+	fn with_placement(mut self, placement: PlacementRule) -> Self {
+		self.placement = placement;
+		self
+	}
+
+	// Seconds it takes to break the tile by hand
+	#[inline]
+	fn with_hardness(mut self, hardness: f32) -> Self {
+		self.hardness = hardness;
+		self
+	}
+
+	#[inline]
+	fn with_sounds(mut self, sounds: TileSounds) -> Self {
+		self.sounds = sounds;
+		self
+	}
+
+	#[inline]
+	fn with_shape(mut self, shape: CollisionShape) -> Self {
+		self.shape = shape;
+		self
+	}
+
+	#[inline]
+	fn with_climbable(mut self) -> Self {
+		self.climbable = true;
+		self
+	}
+
+	#[inline]
+	fn with_fluid(mut self, fluid: FluidData) -> Self {
+		self.fluid = Some(fluid);
+		self
+	}
+
+	// Needs schedule_fall to queue a check and fall_when_unsupported to run it
+	#[inline]
+	fn with_falls(mut self, hooks: TileHooks) -> Self {
+		self.falls = true;
+		self.hooks = hooks;
+		self
+	}
+
+	// Opacity is extra light lost passing into the tile, on top of the one level every step costs
+	#[inline]
+	fn with_light(mut self, opacity: u8, emission: u8) -> Self {
+		self.light_opacity = opacity;
+		self.light_emission = emission.min(MAX_LIGHT);
+		self
+	}
+
+	// Friction scales PhysicsConfig::ground_friction, bounciness is the share of landing speed kept
+	#[inline]
+	fn with_surface(mut self, friction: f32, bounciness: f32) -> Self {
+		self.friction = friction;
+		self.bounciness = bounciness;
+		self
+	}
+
+	#[inline]
+	fn connects_to(&self, other: &TileData) -> bool {
+		self.smooths && other.smooths && (self.smooth_groups & other.smooth_groups) != SmoothGroups::NONE
+	}
 }
 
 #[derive(Component)]
 struct Chunk {
 	tiles: [(Entity, Entity); Chunk::SIZE],
-	foreground_map: HashMap<Entity, i64>,
-	background_map: HashMap<Entity, i64>,
 	pos: ChunkPosition,
 }
 
@@ -371,8 +876,6 @@ impl Chunk {
 	const HEIGHT: usize = 64;
 	const SIZE: usize = Self::WIDTH * Self::HEIGHT;
 
-	const WIDTH_U64: u64 = Self::WIDTH as u64;
-	const HEIGHT_U64: u64 = Self::HEIGHT as u64;
 	const SIZE_U64: u64 = Self::SIZE as u64;
 
 	const WIDTH_I64: i64 = Self::WIDTH as i64;
@@ -384,10 +887,8 @@ impl Chunk {
 			tiles: core::array::from_fn(
 				|i| tiles[i / Self::HEIGHT][i % Self::WIDTH]
 			),
-			foreground_map: HashMap::new(),
-			background_map: HashMap::new(),
 			pos: ChunkPosition::new(x_pos, y_pos)
-		}.update_hashmap()
+		}
 	}
 
 	#[inline]
@@ -397,41 +898,11 @@ impl Chunk {
 			false => None,
 		}
 	}
-
-	fn update_hashmap(mut self) -> Self {
-		self.foreground_map = 
-			|tiles: &[(Entity, Entity); Self::SIZE]| -> HashMap<Entity, i64> {
-				let mut rv: HashMap<Entity, i64> = HashMap::<Entity, i64>::new();
-				for i in 0..tiles.len() {
-					rv.insert(tiles[i].1, i as i64);
-				}
-				rv
-			}(&self.tiles);
-		self.background_map = 
-			|tiles: &[(Entity, Entity); Self::SIZE]| -> HashMap<Entity, i64> {
-				let mut rv: HashMap<Entity, i64> = HashMap::<Entity, i64>::new();
-				for i in 0..tiles.len() {
-					rv.insert(tiles[i].0, i as i64);
-				}
-				rv
-			}(&self.tiles);
-		return self;
-	}
-
-	fn find(&self, to_find: Entity) -> Option<ChunkRelativePosition> {
-		match self.foreground_map.get(&to_find) {
-			Some(found) => Some(ChunkRelativePosition::from_flat(found)),
-			None => match self.background_map.get(&to_find) {
-				Some(found) => Some(ChunkRelativePosition::from_flat(found)),
-				None => None
-			}
-		}
-	}
 }
 
 trait ChunkQuery {
 	fn tile_at(&self, pos: TileAbsolutePosition) -> Option<(Entity, Entity)>;
-	fn find_tile(&self, to_find: Entity) -> Option<TileAbsolutePosition>;
+	fn tile_id_at(&self, tiles: &Query<&Tile>, pos: TileAbsolutePosition, layer: TileLayer) -> Option<TileId>;
 }
 
 impl<'w, 's> ChunkQuery for Query<'w, 's, &Chunk> {
@@ -448,272 +919,3649 @@ impl<'w, 's> ChunkQuery for Query<'w, 's, &Chunk> {
 		None
 	}
 
-	fn find_tile(&self, to_find: Entity) -> Option<TileAbsolutePosition> {
-		for chunk in self {
-			if let Some(pos) = chunk.find(to_find) {
-				return Some((chunk.pos, pos).to_tile_absolute_position());
-			}
+	fn tile_id_at(&self, tiles: &Query<&Tile>, pos: TileAbsolutePosition, layer: TileLayer) -> Option<TileId> {
+		match self.tile_at(pos) {
+			Some(entity) => match tiles.get(layer.select(entity)) {
+				Ok(tile) => Some(tile.id),
+				Err(_) => None,
+			},
+			None => None,
 		}
-		None
 	}
 }
 
+fn write_tile_ids(
+	saving_to: &mut impl Write,
+	ids: impl Iterator<Item = (TileId, TileId)>,
+) -> std::io::Result<()> {
+	for (background, foreground) in ids {
+		saving_to.write_all(&background.to_be_bytes())?;
+		saving_to.write_all(&foreground.to_be_bytes())?;
+	}
+	Ok(())
+}
+
+fn read_tile_id(
+	reading: &mut impl Read,
+) -> std::io::Result<TileId> {
+	let mut data: [u8; std::mem::size_of::<usize>()] =
+		[0; std::mem::size_of::<usize>()];
+	reading.read_exact(&mut data)?;
+	Ok(usize::from_be_bytes(data))
+}
+
+// Fluid levels follow the tile ids, one byte per tile
 fn write_chunk(
 	chunk: &Chunk,
 	tiles: &Query<&Tile>,
+	fluids: &FluidLevels,
+	scheduler: &TickScheduler,
 	file: std::path::PathBuf,
-) {
-	let mut saving_to = std::fs::File::create(file).unwrap();
+) -> std::io::Result<()> {
+	let mut saving_to = std::fs::File::create(file)?;
 
-	for entity in chunk.tiles {
-		if let Ok(found) = tiles.get(entity.0) {
-			match saving_to.write_all(&found.id.to_be_bytes()) {
-				Ok(_) => (),
-				Err(_) => return,//TODO: HANDLE THIS BETTER
-			};
+	write_tile_ids(&mut saving_to, chunk.tiles.iter().filter_map(
+		|entity| match (tiles.get(entity.0), tiles.get(entity.1)) {
+			(Ok(background), Ok(foreground)) => Some((background.id, foreground.id)),
+			_ => None,
 		}
-		if let Ok(found) = tiles.get(entity.1) {
-			match saving_to.write_all(&found.id.to_be_bytes()) {
-				Ok(_) => (),
-				Err(_) => return,//TODO: HANDLE THIS BETTER
-			};
+	))?;
+
+	let levels: Vec<u8> = (0..Chunk::SIZE_I64).map(
+		|i| fluids.level((chunk.pos, ChunkRelativePosition::from_flat(&i)).to_tile_absolute_position())
+	).collect();
+	saving_to.write_all(&levels)?;
+	write_scheduled_ticks(&mut saving_to, scheduler, chunk.pos)
+}
+
+// Scheduled ticks, as a count followed by (flat index, layer, ticks left)
+fn write_scheduled_ticks(
+	saving_to: &mut impl Write,
+	scheduler: &TickScheduler,
+	chunk: ChunkPosition,
+) -> std::io::Result<()> {
+	let scheduled: Vec<(TileAbsolutePosition, TileLayer, u64)> = scheduler.scheduled_in(chunk);
+	let mut data: Vec<u8> = Vec::new();
+	data.extend_from_slice(&(scheduled.len() as u64).to_be_bytes());
+	for (pos, layer, ticks_left) in scheduled {
+		data.extend_from_slice(&(pos.to_positions().1.to_flat() as u64).to_be_bytes());
+		data.push(match layer {
+			TileLayer::Background => 0,
+			TileLayer::Foreground => 1,
+		});
+		data.extend_from_slice(&ticks_left.to_be_bytes());
+	}
+	saving_to.write_all(&data)
+}
+
+// Older chunk files just end early, which reads as no scheduled ticks
+fn read_scheduled_ticks(
+	reading: &mut impl Read,
+	chunk: ChunkPosition,
+) -> std::io::Result<Vec<(TileAbsolutePosition, TileLayer, u64)>> {
+	let mut word: [u8; 8] = [0; 8];
+	let mut layer: [u8; 1] = [0; 1];
+	reading.read_exact(&mut word)?;
+	let count: u64 = u64::from_be_bytes(word);
+	let mut scheduled: Vec<(TileAbsolutePosition, TileLayer, u64)> = Vec::new();
+	for _ in 0..count {
+		reading.read_exact(&mut word)?;
+		let local: ChunkRelativePosition = ChunkRelativePosition::from_flat(&(u64::from_be_bytes(word) as i64 % Chunk::SIZE_I64));
+		reading.read_exact(&mut layer)?;
+		reading.read_exact(&mut word)?;
+		scheduled.push((
+			(chunk, local).to_tile_absolute_position(),
+			match layer[0] {
+				0 => TileLayer::Background,
+				_ => TileLayer::Foreground,
+			},
+			u64::from_be_bytes(word),
+		));
+	}
+	Ok(scheduled)
+}
+
+// Tile ids as (background, foreground) pairs, fluid levels and scheduled ticks, checked before anything is replaced
+fn read_chunk(
+	reading: &mut impl Read,
+	pos: ChunkPosition,
+) -> std::io::Result<(Vec<TileId>, [u8; Chunk::SIZE], Vec<(TileAbsolutePosition, TileLayer, u64)>)> {
+	let mut tile_data: Vec<TileId> = Vec::with_capacity(Chunk::SIZE * 2);
+	for _ in 0..Chunk::SIZE * 2 {
+		let id: TileId = read_tile_id(reading)?;
+		if id >= TileIds::BLOCKS {
+			return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, format!("unknown tile id {}", id)));
 		}
+		tile_data.push(id);
 	}
+
+	// Chunks saved before fluids existed have no levels, so their fluids start full
+	let mut levels: [u8; Chunk::SIZE] = [FluidLevels::MAX; Chunk::SIZE];
+	let _ = reading.read_exact(&mut levels);
+
+	Ok((tile_data, levels, read_scheduled_ticks(reading, pos).unwrap_or_default()))
 }
 
 fn replace_chunk(
 	tile_change_queue: &mut ResMut<TileChangeQueue>,
+	fluids: &mut ResMut<FluidLevels>,
+	scheduler: &mut ResMut<TickScheduler>,
+	tile_ids: &TileIds,
 	file: std::path::PathBuf,
 	pos: ChunkPosition,
-) {
-	let mut reading = std::fs::File::open(file).unwrap();
+) -> std::io::Result<()> {
+	let mut reading = std::fs::File::open(file)?;
+	let (tile_data, levels, scheduled) = read_chunk(&mut reading, pos)?;
 
-	let tile_data: [usize; Chunk::SIZE * 2] = core::array::from_fn(
-		|_| -> usize {
-			let mut data: [u8; std::mem::size_of::<usize>()] =
-				[0; std::mem::size_of::<usize>()];
-			match reading.read(&mut data) {
-				Ok(_) => (),
-				Err(_) => panic!(),
-			}
-			return usize::from_be_bytes(data);
-		}
-	);
+	scheduler.clear_chunk(pos);
+	for (tile_pos, layer, ticks_left) in scheduled {
+		scheduler.schedule(tile_pos, layer, ticks_left);
+	}
 
 	for x in 0..=(Chunk::HEIGHT-1) {
 		for y in 0..=(Chunk::WIDTH-1) {
+			let tile_pos: TileAbsolutePosition =
+				(pos, ChunkRelativePosition::new(x as i64, y as i64)).to_tile_absolute_position();
+			let foreground: TileId = tile_data[((x + (y * Chunk::WIDTH)) * 2) + 1];
+			fluids.set(tile_pos, foreground, match tile_ids.by_id(foreground).fluid {
+				Some(_) => levels[x + (y * Chunk::WIDTH)],
+				None => 0,
+			});
+			tile_change_queue.push(
+				(tile_data[(x + (y * Chunk::WIDTH)) * 2], TileLayer::Background, tile_pos, TileChangeCause::Load)
+			);
 			tile_change_queue.push(
-				(tile_data[((x + (y * Chunk::WIDTH)) * 2) + 1], true,
-					(pos, ChunkRelativePosition::new(x as i64, y as i64)).to_tile_absolute_position()
-				)
+				(tile_data[((x + (y * Chunk::WIDTH)) * 2) + 1], TileLayer::Foreground, tile_pos, TileChangeCause::Load)
 			);
 		}
 	}
+	Ok(())
 }
 
-fn find_chunk<'s>(
-	search_through: &'s Query<&Chunk>,
-	pos: ChunkPosition,
-) -> Option<&'s Chunk> {
-	for chunk in search_through {
-		if chunk.pos == pos {
-			return Some(chunk);
+#[cfg(test)]
+mod chunk_file_tests {
+	use super::*;
+
+	fn saved_tiles(ids: impl Iterator<Item = (TileId, TileId)>) -> Vec<u8> {
+		let mut data: Vec<u8> = Vec::new();
+		write_tile_ids(&mut data, ids).unwrap();
+		data
+	}
+
+	#[test]
+	fn reads_chunks_without_levels_or_ticks() {
+		let data: Vec<u8> = saved_tiles((0..Chunk::SIZE).map(|i| (TileIds::AIR, i % TileIds::BLOCKS)));
+		let (tile_data, levels, scheduled) = read_chunk(&mut data.as_slice(), ChunkPosition::new(0, 0)).unwrap();
+		assert_eq!(tile_data[3], TileIds::DIRT);
+		assert_eq!(levels[0], FluidLevels::MAX);
+		assert!(scheduled.is_empty());
+	}
+
+	#[test]
+	fn rejects_unknown_tile_ids() {
+		let data: Vec<u8> = saved_tiles((0..Chunk::SIZE).map(|i| (TileIds::DIRT, if i == 7 { TileIds::BLOCKS } else { TileIds::DIRT })));
+		match read_chunk(&mut data.as_slice(), ChunkPosition::new(0, 0)) {
+			Err(error) => assert_eq!(error.kind(), std::io::ErrorKind::InvalidData),
+			Ok(_) => panic!("read a chunk with an unknown tile id"),
 		}
 	}
-	None
+
+	#[test]
+	fn rejects_truncated_chunks() {
+		let data: Vec<u8> = saved_tiles((0..Chunk::SIZE / 2).map(|_| (TileIds::DIRT, TileIds::DIRT)));
+		assert!(read_chunk(&mut data.as_slice(), ChunkPosition::new(0, 0)).is_err());
+	}
 }
 
-fn debug_input(
-	mut update_tiles: ResMut<TilesShouldUpdate>,
-	mut tile_update_queue: ResMut<TileChangeQueue>,
-	keys: Res<ButtonInput<KeyCode>>,
-	chunks: Query<&Chunk>,
-	tiles: Query<&Tile>,
-) {
+#[derive(Clone)]
+struct Schematic {
+	width: usize,
+	height: usize,
+	tiles: Vec<(TileId, TileId)>,
+}
+
+impl Schematic {
+	const EXTENSION: &'static str = "schematic";
+	const MAX_SIDE: usize = 256;
+	const MAX_NAME: usize = 32;
+
+	fn capture(
+		chunks: &Query<&Chunk>,
+		tiles: &Query<&Tile>,
+		region: TileRegion,
+	) -> Self {
+		Self {
+			width: (region.max.0.x - region.min.0.x + 1) as usize,
+			height: (region.max.0.y - region.min.0.y + 1) as usize,
+			tiles: region.iter().map(|pos| (
+				chunks.tile_id_at(tiles, pos, TileLayer::Background).unwrap_or(TileIds::AIR),
+				chunks.tile_id_at(tiles, pos, TileLayer::Foreground).unwrap_or(TileIds::AIR),
+			)).collect(),
+		}
+	}
+
+	#[inline]
+	fn at(&self, x: usize, y: usize) -> (TileId, TileId) { self.tiles[x + y * self.width] }
+
+	// Quarter turn clockwise
+	fn rotated(&self) -> Self {
+		Self {
+			width: self.height,
+			height: self.width,
+			tiles: (0..self.width).rev().flat_map(
+				|x| (0..self.height).map(move |y| (x, y))
+			).map(|(x, y)| self.at(x, y)).collect(),
+		}
+	}
+
+	fn mirrored(&self) -> Self {
+		Self {
+			width: self.width,
+			height: self.height,
+			tiles: (0..self.height).flat_map(
+				|y| (0..self.width).rev().map(move |x| (x, y))
+			).map(|(x, y)| self.at(x, y)).collect(),
+		}
+	}
+
+	fn paste(&self, queue: &mut TileChangeQueue, origin: TileAbsolutePosition, cause: TileChangeCause) {
+		for y in 0..self.height {
+			for x in 0..self.width {
+				let (background, foreground) = self.at(x, y);
+				let pos: TileAbsolutePosition = origin + (x as i64, y as i64);
+				queue.push((background, TileLayer::Background, pos, cause));
+				queue.push((foreground, TileLayer::Foreground, pos, cause));
+			}
+		}
+	}
+
+	fn directory() -> std::path::PathBuf {
+		let mut path = std::env::current_dir().unwrap();
+		path.push("save");
+		path.push("schematics");
+		path
+	}
+
+	fn save(&self, name: &str) -> std::io::Result<()> {
+		let mut path = Self::directory();
+		std::fs::create_dir_all(&path)?;
+		path.push(format!("{}.{}", name, Self::EXTENSION));
+		let mut saving_to = std::fs::File::create(path)?;
+
+		saving_to.write_all(&self.width.to_be_bytes())?;
+		saving_to.write_all(&self.height.to_be_bytes())?;
+		write_tile_ids(&mut saving_to, self.tiles.iter().copied())
+	}
+
+	fn load(name: &str) -> std::io::Result<Self> {
+		let mut path = Self::directory();
+		path.push(format!("{}.{}", name, Self::EXTENSION));
+		let mut reading = std::fs::File::open(path)?;
+
+		let width: usize = read_tile_id(&mut reading)?;
+		let height: usize = read_tile_id(&mut reading)?;
+		if width == 0 || height == 0 || width > Self::MAX_SIDE || height > Self::MAX_SIDE {
+			return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, format!("bad schematic size {}x{}", width, height)));
+		}
+		let area: usize = width.checked_mul(height)
+			.ok_or(std::io::Error::new(std::io::ErrorKind::InvalidData, "schematic size overflows"))?;
+		let mut tiles: Vec<(TileId, TileId)> = Vec::with_capacity(area);
+		for _ in 0..area {
+			let background: TileId = read_tile_id(&mut reading)?;
+			let foreground: TileId = read_tile_id(&mut reading)?;
+			if background >= TileIds::BLOCKS || foreground >= TileIds::BLOCKS {
+				return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, format!("unknown tile id {}", background.max(foreground))));
+			}
+			tiles.push((background, foreground));
+		}
+		Ok(Self { width: width, height: height, tiles: tiles })
+	}
+
+	// Saved schematic names, sorted so cycling through them is stable
+	fn names() -> Vec<String> {
+		let mut names: Vec<String> = match std::fs::read_dir(Self::directory()) {
+			Ok(entries) => entries
+				.filter_map(|entry| entry.ok())
+				.map(|entry| entry.path())
+				.filter(|path| path.extension().is_some_and(|extension| extension == Self::EXTENSION))
+				.filter_map(|path| path.file_stem().map(|stem| stem.to_string_lossy().into_owned()))
+				.collect(),
+			Err(_) => Vec::new(),
+		};
+		names.sort();
+		names
+	}
+
+	#[inline]
+	fn valid_name_char(c: char) -> bool { c.is_ascii_alphanumeric() || c == '-' || c == '_' }
+}
+
+#[derive(Resource, Default)]
+struct Clipboard {
+	selection: Option<TileRegion>,
+	schematic: Option<Schematic>,
+	// Name typed so far while the save prompt is open
+	naming: Option<String>,
+	browsing: usize,
+	status: Option<String>,
+}
+
+#[derive(Component)]
+struct SchematicPrompt {
+	timer: Timer,
+}
+
+// Runs right after input is gathered so typed names don't also trigger hotkeys
+fn schematic_name_prompt(
+	time: Res<Time>,
+	mut typed: MessageReader<KeyboardInput>,
+	mut keys: ResMut<ButtonInput<KeyCode>>,
+	mut clipboard: ResMut<Clipboard>,
+	mut prompt: Query<(&mut Text, &mut Visibility, &mut SchematicPrompt)>,
+) {
+	let clipboard = &mut *clipboard;
+	if let Some(name) = &mut clipboard.naming {
+		let mut finished: Option<bool> = None;
+		for input in typed.read() {
+			if input.state != ButtonState::Pressed {
+				continue;
+			}
+			match &input.logical_key {
+				Key::Enter => finished = Some(true),
+				Key::Escape => finished = Some(false),
+				Key::Backspace => { name.pop(); },
+				Key::Character(characters) => for c in characters.chars().filter(|c| Schematic::valid_name_char(*c)) {
+					if name.len() < Schematic::MAX_NAME {
+						name.push(c);
+					}
+				},
+				_ => {},
+			}
+		}
+		keys.reset_all();
+		match finished {
+			Some(true) if !name.is_empty() => {
+				if let Some(schematic) = &clipboard.schematic {
+					clipboard.status = Some(match schematic.save(name) {
+						Ok(()) => format!("Saved {}", name),
+						Err(error) => format!("Failed to save {}: {}", name, error),
+					});
+				}
+				clipboard.naming = None;
+			},
+			Some(false) => clipboard.naming = None,
+			_ => {},
+		}
+	} else {
+		typed.clear();
+	}
+
+	if let Ok((mut text, mut visibility, mut prompt)) = prompt.single_mut() {
+		if let Some(name) = &clipboard.naming {
+			text.0 = format!("Save schematic as: {}_", name);
+			*visibility = Visibility::Inherited;
+			prompt.timer.reset();
+		} else if let Some(status) = clipboard.status.take() {
+			text.0 = status;
+			*visibility = Visibility::Inherited;
+			prompt.timer.reset();
+		}
+		prompt.timer.tick(time.delta());
+		if prompt.timer.just_finished() {
+			*visibility = Visibility::Hidden;
+		}
+	}
+}
+
+fn clipboard_input(
+	keys: Res<ButtonInput<KeyCode>>,
+	fixed_time: Res<Time<Fixed>>,
+	windows: Query<&Window, With<bevy::window::PrimaryWindow>>,
+	mut clipboard: ResMut<Clipboard>,
+	mut update_queue: ResMut<TileChangeQueue>,
+	mut rejections: MessageWriter<PlacementRejected>,
+	tile_ids: Res<TileIds>,
+	pixel_projection: Query<&Projection>,
+	chunks: Query<&Chunk>,
+	tiles: Query<&Tile>,
+	player: Query<&Mob, With<Player>>,
+	other_mobs: Query<&Mob, Without<Player>>,
+) {
+	let ctrl: bool = keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+	if ctrl && keys.just_pressed(KeyCode::KeyC) {
+		if let Some(selection) = clipboard.selection {
+			clipboard.schematic = Some(Schematic::capture(&chunks, &tiles, selection));
+		}
+	} else if ctrl && keys.just_pressed(KeyCode::KeyS) {
+		if clipboard.schematic.is_some() {
+			clipboard.naming = Some(String::new());
+		}
+	} else if ctrl && keys.just_pressed(KeyCode::KeyO) {
+		let names: Vec<String> = Schematic::names();
+		if !names.is_empty() {
+			let name: &String = &names[clipboard.browsing % names.len()];
+			clipboard.browsing = (clipboard.browsing + 1) % names.len();
+			clipboard.status = Some(match Schematic::load(name) {
+				Ok(schematic) => {
+					clipboard.schematic = Some(schematic);
+					format!("Loaded {}", name)
+				},
+				Err(error) => format!("Failed to load {}: {}", name, error),
+			});
+		}
+	} else if keys.just_pressed(KeyCode::KeyE) {
+		clipboard.schematic = clipboard.schematic.as_ref().map(|schematic| schematic.rotated());
+	} else if keys.just_pressed(KeyCode::KeyF) {
+		clipboard.schematic = clipboard.schematic.as_ref().map(|schematic| schematic.mirrored());
+	} else if ctrl && keys.just_pressed(KeyCode::KeyV) {
+		if let Some(schematic) = &clipboard.schematic {
+		if let Ok(window) = windows.single() {
+		if let Ok(projection) = pixel_projection.single() {
+		if let Ok(mob) = player.single() {
+		if let Some(origin) = cursor_tile_position(window, projection, mob.interpolated_position(fixed_time.overstep_fraction())) {
+			if schematic.width * schematic.height > EDIT_AREA_LIMIT {
+				rejections.write(PlacementRejected { pos: origin, reason: PlacementRejection::AreaTooLarge });
+			} else {
+				let mut candidates: TileChangeQueue = TileChangeQueue::default();
+				schematic.paste(&mut candidates, origin, TileChangeCause::Player);
+				queue_player_edits(&candidates, &mut update_queue, &mut rejections, mob, &other_mobs, &tile_ids, &chunks, &tiles);
+			}
+		}}}}}
+	}
+}
+
+fn find_chunk<'s>(
+	search_through: &'s Query<&Chunk>,
+	pos: ChunkPosition,
+) -> Option<&'s Chunk> {
+	search_through.iter().find(|chunk| chunk.pos == pos)
+}
+
+fn debug_input(
+	mut tile_update_queue: ResMut<TileChangeQueue>,
+	mut fluids: ResMut<FluidLevels>,
+	mut scheduler: ResMut<TickScheduler>,
+	mut world_time: ResMut<WorldTime>,
+	tile_ids: Res<TileIds>,
+	keys: Res<ButtonInput<KeyCode>>,
+	chunks: Query<&Chunk>,
+	tiles: Query<&Tile>,
+) {
 	if let Some(chunk) = find_chunk(&chunks, ChunkPosition::new(-1, 2)) {
 		if keys.just_pressed(KeyCode::KeyR) {
 			let mut write_path = std::env::current_dir().unwrap();
 			write_path.push("save");
 			write_path.push(format!("{}-{}.chunk", -1, 2));
-			write_chunk(&chunk, &tiles, write_path);
+			if let Err(error) = write_chunk(chunk, &tiles, &fluids, &scheduler, write_path) {
+				warn!("Couldn't save chunk: {}", error);
+			}
+			if let Err(error) = world_time.save() {
+				warn!("Couldn't save world time: {}", error);
+			}
 		} else if keys.just_pressed(KeyCode::KeyT) {
 			let mut read_path = std::env::current_dir().unwrap();
 			read_path.push("save");
 			read_path.push(format!("{}-{}.chunk", -1, 2));
-			replace_chunk(&mut tile_update_queue, read_path, ChunkPosition::new(-1, 2));
-			update_tiles.should_update = true;
+			if let Err(error) = replace_chunk(&mut tile_update_queue, &mut fluids, &mut scheduler, &tile_ids, read_path, ChunkPosition::new(-1, 2)) {
+				warn!("Couldn't load chunk: {}", error);
+			}
+			if let Ok(loaded) = WorldTime::load() {
+				world_time.ticks = loaded.ticks;
+			}
+		}
+	}
+}
+
+fn update_camera (
+	fixed_time: Res<Time<Fixed>>,
+	mut cam: Query<&mut Transform, (With<Camera>, Without<Player>)>,
+	mut player: Query<(&Mob, &mut Transform), With<Player>>,
+) {
+	if let Ok(mut transfem) = cam.single_mut() {
+		if let Ok((mob, mut player_transfem)) = player.single_mut() {
+			let position: Vec2 = mob.interpolated_position(fixed_time.overstep_fraction());
+			player_transfem.translation.x = position.x * 8.0;
+			player_transfem.translation.y = position.y * 8.0 + 12.0;
+			transfem.translation.x = position.x * 8.0;
+			transfem.translation.y = position.y * 8.0;
+		}
+	}
+}
+
+fn player_input (
+	windows: Query<&Window, With<bevy::window::PrimaryWindow>>,
+	keys: Res<ButtonInput<KeyCode>>,
+	fixed_time: Res<Time<Fixed>>,
+	mouse_keys: Res<ButtonInput<MouseButton>>,
+	mut update_queue: ResMut<TileChangeQueue>,
+	mut history: ResMut<EditHistory>,
+	mut clipboard: ResMut<Clipboard>,
+	mut rejections: MessageWriter<PlacementRejected>,
+	tile_ids: Res<TileIds>,
+	pixel_projection: Query<&Projection>,
+	chunks: Query<&Chunk>,
+	tiles: Query<&Tile>,
+	other_mobs: Query<&Mob, Without<Player>>,
+	mut query: Query<(&mut Player, &mut Mob)>,
+) {
+	if let Ok((mut player, mut mob)) = query.single_mut() {
+		if keys.pressed(KeyCode::Space) {
+			mob.jump_state = JumpState::TryJump;
+		} else {
+			mob.jump_state = JumpState::None;
+		}
+		mob.walk_state = WalkState::None;
+		if keys.pressed(KeyCode::KeyA) {
+			mob.walk_state = WalkState::TryLeft;
+		}
+		if keys.pressed(KeyCode::KeyD) {
+			mob.walk_state = WalkState::TryRight;
+		}
+		let ctrl: bool = keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+		// Ctrl+S saves the clipboard, so it shouldn't also drop the player through platforms
+		let down: bool = keys.pressed(KeyCode::KeyS) && !ctrl;
+		mob.drop_through = down;
+		mob.climb_direction = ClimbDirection::None;
+		if keys.pressed(KeyCode::KeyW) {
+			mob.climb_direction = ClimbDirection::TryUp;
+		} else if down {
+			mob.climb_direction = ClimbDirection::TryDown;
+		}
+		if keys.just_pressed(KeyCode::KeyZ) && !ctrl {
+			if player.selected_block > TileIds::AIR + 1 {
+				player.selected_block -= 1;
+			}
+		} else if keys.just_pressed(KeyCode::KeyX) {
+			if player.selected_block < TileIds::BLOCKS - 1 {
+				player.selected_block += 1;
+			}
+		}
+
+		let window = windows.single().unwrap();
+
+		if mouse_keys.any_just_pressed([MouseButton::Left, MouseButton::Right]) {
+			history.begin_group();
+		}
+		if mouse_keys.any_just_released([MouseButton::Left, MouseButton::Right]) {
+			history.end_group();
+		}
+
+		if keys.just_pressed(KeyCode::Digit1) {
+			player.tool = EditTool::Single;
+		} else if keys.just_pressed(KeyCode::Digit2) {
+			player.tool = EditTool::Line;
+		} else if keys.just_pressed(KeyCode::Digit3) {
+			player.tool = EditTool::Rectangle;
+		} else if keys.just_pressed(KeyCode::Digit4) {
+			player.tool = EditTool::Replace;
+		} else if keys.just_pressed(KeyCode::Digit5) {
+			player.tool = EditTool::Flood;
+		} else if keys.just_pressed(KeyCode::Digit6) {
+			player.tool = EditTool::Select;
+		}
+
+		let buttons: [MouseButton; 2] = [MouseButton::Left, MouseButton::Right];
+		if mouse_keys.any_pressed(buttons) || mouse_keys.any_just_released(buttons) {
+		if let Ok(projection) = pixel_projection.single() {
+		if let Some(tile_mouse_position) = cursor_tile_position(window, projection, mob.interpolated_position(fixed_time.overstep_fraction())) {
+			let placing: TileId = match mouse_keys.pressed(MouseButton::Left) || mouse_keys.just_released(MouseButton::Left) {
+				true => player.selected_block,
+				false => TileIds::AIR,
+			};
+
+			match player.tool {
+				EditTool::Single => if mouse_keys.pressed(MouseButton::Left) {
+					let edit: (TileId, TileLayer, TileAbsolutePosition) = (placing, TileLayer::Foreground, tile_mouse_position);
+					match validate_tile_edit(&mob, other_mobs.iter(), edit, &tile_ids, &chunks, &tiles) {
+						Ok(()) => update_queue.push((edit.0, edit.1, edit.2, TileChangeCause::Player)),
+						Err(reason) => if mouse_keys.just_pressed(MouseButton::Left) {
+							rejections.write(PlacementRejected { pos: tile_mouse_position, reason: reason });
+						},
+					}
+				},
+				EditTool::Flood => if mouse_keys.any_just_pressed(buttons) {
+					let mut candidates: TileChangeQueue = TileChangeQueue::default();
+					match candidates.flood_fill(
+						&chunks, &tiles, tile_mouse_position, placing, TileLayer::Foreground, TileChangeCause::Player, FLOOD_FILL_LIMIT
+					) {
+						Ok(_) => queue_player_edits(&candidates, &mut update_queue, &mut rejections, &mob, &other_mobs, &tile_ids, &chunks, &tiles),
+						Err(reason) => {
+							rejections.write(PlacementRejected { pos: tile_mouse_position, reason: reason });
+						},
+					}
+				},
+				EditTool::Line | EditTool::Rectangle | EditTool::Replace | EditTool::Select => {
+					if mouse_keys.any_just_pressed(buttons) {
+						player.drag_start = Some(tile_mouse_position);
+					}
+					if mouse_keys.any_just_released(buttons) {
+					if let Some(drag_start) = player.drag_start.take() {
+						let region: TileRegion = TileRegion::from_corners(drag_start, tile_mouse_position);
+						let mut candidates: TileChangeQueue = TileChangeQueue::default();
+						if player.tool != EditTool::Select && region.area() > EDIT_AREA_LIMIT {
+							rejections.write(PlacementRejected { pos: tile_mouse_position, reason: PlacementRejection::AreaTooLarge });
+						} else {
+							match player.tool {
+								EditTool::Line =>
+									candidates.line(drag_start, tile_mouse_position, placing, TileLayer::Foreground, TileChangeCause::Player),
+								EditTool::Rectangle =>
+									candidates.fill(region, placing, TileLayer::Foreground, TileChangeCause::Player),
+								EditTool::Select =>
+									clipboard.selection = Some(region),
+								_ => if let Some(replacing) = chunks.tile_id_at(&tiles, drag_start, TileLayer::Foreground) {
+									candidates.replace(&chunks, &tiles, region, replacing, placing, TileLayer::Foreground, TileChangeCause::Player);
+								},
+							}
+							queue_player_edits(&candidates, &mut update_queue, &mut rejections, &mob, &other_mobs, &tile_ids, &chunks, &tiles);
+						}
+					}}
+				},
+			}
+		}}}
+	}
+}
+
+// center should be where the camera is drawn, the interpolated player position
+fn cursor_tile_position(
+	window: &Window,
+	pixel_projection: &Projection,
+	center: Vec2,
+) -> Option<TileAbsolutePosition> {
+	let cursor: Vec2 = window.cursor_position()?;
+	let projection: OrthographicProjection = match pixel_projection {
+		Projection::Orthographic(ortho) => ortho.clone(),
+		_ => OrthographicProjection::default_2d()
+	};
+//	let play_width: f32 =
+//		projection.area.width() as f32 * 0.5;
+//	let play_height: f32 =
+//		projection.area.height() as f32 * 0.5;
+	let real_mouse_x: f32 =
+		((cursor.x - (window.width() * 0.5)) * projection.scale).round();
+	let real_mouse_y: f32 =
+		-((cursor.y - (window.height() * 0.5)) * projection.scale).round();
+
+//	if real_mouse_x <= play_width && real_mouse_y <= play_height {
+	Some(TileAbsolutePosition::new(
+		(center.x + (real_mouse_x / 8.0)).round() as i64,
+		(center.y + (real_mouse_y / 8.0)).round() as i64
+	))
+//	}
+}
+
+const FLOOD_FILL_LIMIT: usize = 4096;
+// Most tiles a line, rectangle or replace can touch at once
+const EDIT_AREA_LIMIT: usize = 1024;
+
+#[derive(Component)]
+struct BreakOverlay;
+
+fn break_tiles(
+	time: Res<Time>,
+	fixed_time: Res<Time<Fixed>>,
+	mouse_keys: Res<ButtonInput<MouseButton>>,
+	windows: Query<&Window, With<bevy::window::PrimaryWindow>>,
+	pixel_projection: Query<&Projection>,
+	tile_ids: Res<TileIds>,
+	chunks: Query<&Chunk>,
+	tiles: Query<&Tile>,
+	mut update_queue: ResMut<TileChangeQueue>,
+	mut rejections: MessageWriter<PlacementRejected>,
+	mut player: Query<(&mut Player, &Mob)>,
+	mut overlay: Query<(&mut Transform, &mut Sprite, &mut Visibility), (With<BreakOverlay>, Without<Player>)>,
+) {
+	if let Ok((mut player, mob)) = player.single_mut() {
+	if let Ok((mut transfem, mut sprite, mut visibility)) = overlay.single_mut() {
+		let target: Option<TileAbsolutePosition> = match (player.tool, mouse_keys.pressed(MouseButton::Right), windows.single(), pixel_projection.single()) {
+			(EditTool::Single, true, Ok(window), Ok(projection)) => cursor_tile_position(window, projection, mob.interpolated_position(fixed_time.overstep_fraction())),
+			_ => None,
+		};
+		let target: Option<TileAbsolutePosition> = target.filter(|pos| {
+			let edit: (TileId, TileLayer, TileAbsolutePosition) = (TileIds::AIR, TileLayer::Foreground, *pos);
+			match validate_tile_edit(mob, std::iter::empty(), edit, &tile_ids, &chunks, &tiles) {
+				Ok(()) => true,
+				Err(reason) => {
+					if mouse_keys.just_pressed(MouseButton::Right) {
+						rejections.write(PlacementRejected { pos: *pos, reason: reason });
+					}
+					false
+				},
+			}
+		});
+
+		// Moving onto a different tile or letting go starts the break over
+		player.breaking = match (target, player.breaking) {
+			(Some(pos), Some((breaking_pos, progress))) if pos == breaking_pos => Some((pos, progress)),
+			(Some(pos), _) => Some((pos, 0.0)),
+			(None, _) => None,
+		};
+
+		if let Some((pos, progress)) = player.breaking {
+		if let Some(breaking) = chunks.tile_id_at(&tiles, pos, TileLayer::Foreground) {
+			let hardness: f32 = tile_ids.by_id(breaking).hardness;
+			let progress: f32 = match hardness > 0.0 {
+				true => progress + time.delta_secs() / hardness,
+				false => 1.0,
+			};
+			if progress >= 1.0 {
+				update_queue.push((TileIds::AIR, TileLayer::Foreground, pos, TileChangeCause::Player));
+				player.breaking = None;
+			} else {
+				player.breaking = Some((pos, progress));
+			}
+		}}
+
+		match player.breaking {
+			Some((pos, progress)) => {
+				transfem.translation.x = pos.0.x as f32 * 8.0;
+				transfem.translation.y = pos.0.y as f32 * 8.0;
+				sprite.color = Color::srgba(1.0, 1.0, 1.0, 0.25 + progress * 0.75);
+				*visibility = Visibility::Inherited;
+			},
+			None => *visibility = Visibility::Hidden,
+		}
+	}}
+}
+
+const PLAYER_REACH: f32 = 8.0;
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum PlacementRule {
+	Anywhere,
+	Adjacent,
+	Supported,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum PlacementRejection {
+	OutOfReach,
+	OutsideWorld,
+	Occupied,
+	NothingToBreak,
+	InsideMob,
+	NothingAdjacent,
+	NoSupport,
+	AreaTooLarge,
+}
+
+impl PlacementRejection {
+	#[inline]
+	fn reason(&self) -> &'static str {
+		match self {
+			PlacementRejection::OutOfReach => "Too far away",
+			PlacementRejection::OutsideWorld => "Outside the world",
+			PlacementRejection::Occupied => "Something is already there",
+			PlacementRejection::NothingToBreak => "Nothing to break",
+			PlacementRejection::InsideMob => "Something is in the way",
+			PlacementRejection::NothingAdjacent => "Needs a block next to it",
+			PlacementRejection::NoSupport => "Needs a solid block below it",
+			PlacementRejection::AreaTooLarge => "Area is too large",
+		}
+	}
+}
+
+#[derive(Message, Copy, Clone)]
+struct PlacementRejected {
+	pos: TileAbsolutePosition,
+	reason: PlacementRejection,
+}
+
+#[derive(Component)]
+struct RejectionText {
+	timer: Timer,
+}
+
+fn validate_tile_edit<'a>(
+	editor: &Mob,
+	mut mobs: impl Iterator<Item = &'a Mob>,
+	(id, layer, pos): (TileId, TileLayer, TileAbsolutePosition),
+	tile_ids: &TileIds,
+	chunks: &Query<&Chunk>,
+	tiles: &Query<&Tile>,
+) -> Result<(), PlacementRejection> {
+	// Centre to centre, so reach is the same in every direction
+	if editor.center().distance(Vec2::new(pos.0.x as f32 + 0.5, pos.0.y as f32 + 0.5)) > PLAYER_REACH {
+		return Err(PlacementRejection::OutOfReach);
+	}
+	let current: TileId = match chunks.tile_id_at(tiles, pos, layer) {
+		Some(id) => id,
+		None => return Err(PlacementRejection::OutsideWorld),
+	};
+	if id == TileIds::AIR {
+		return match current {
+			TileIds::AIR => Err(PlacementRejection::NothingToBreak),
+			_ => Ok(()),
+		};
+	}
+	if current != TileIds::AIR && tile_ids.by_id(current).fluid.is_none() {
+		return Err(PlacementRejection::Occupied);
+	}
+
+	let placing: &TileData = tile_ids.by_id(id);
+	if placing.solid && layer == TileLayer::Foreground {
+		if editor.occupies(pos) || mobs.any(|mob| mob.occupies(pos)) {
+			return Err(PlacementRejection::InsideMob);
+		}
+	}
+
+	let is_filled = |at: TileAbsolutePosition, layer: TileLayer| -> bool {
+		match chunks.tile_id_at(tiles, at, layer) {
+			Some(found) => found != TileIds::AIR,
+			None => false,
+		}
+	};
+	match placing.placement {
+		PlacementRule::Anywhere => Ok(()),
+		PlacementRule::Adjacent => match
+			is_filled(pos + (0, 1), layer) ||
+			is_filled(pos + (0, -1), layer) ||
+			is_filled(pos + (1, 0), layer) ||
+			is_filled(pos + (-1, 0), layer) ||
+			is_filled(pos, TileLayer::Background)
+		{
+			true => Ok(()),
+			false => Err(PlacementRejection::NothingAdjacent),
+		},
+		PlacementRule::Supported => match chunks.tile_id_at(tiles, pos + (0, -1), TileLayer::Foreground) {
+			Some(below) if tile_ids.by_id(below).solid => Ok(()),
+			_ => Err(PlacementRejection::NoSupport),
+		},
+	}
+}
+
+// Runs a batch of player edits through validate_tile_edit and queues the ones that pass.
+// Edits accepted earlier in the batch count as neighbours, so a shape can be built out into open air.
+fn queue_player_edits(
+	candidates: &TileChangeQueue,
+	update_queue: &mut TileChangeQueue,
+	rejections: &mut MessageWriter<PlacementRejected>,
+	editor: &Mob,
+	mobs: &Query<&Mob, Without<Player>>,
+	tile_ids: &TileIds,
+	chunks: &Query<&Chunk>,
+	tiles: &Query<&Tile>,
+) {
+	let mut accepted: HashSet<TileAbsolutePosition> = HashSet::new();
+	let mut waiting: Vec<(TileId, TileLayer, TileAbsolutePosition, PlacementRejection)> = Vec::new();
+	for (id, layer, pos, cause) in &candidates.queue {
+		match validate_tile_edit(editor, mobs.iter(), (*id, *layer, *pos), tile_ids, chunks, tiles) {
+			Ok(()) => {
+				update_queue.push((*id, *layer, *pos, *cause));
+				accepted.insert(*pos);
+			},
+			Err(reason) => waiting.push((*id, *layer, *pos, reason)),
+		}
+	}
+
+	loop {
+		let before: usize = waiting.len();
+		waiting.retain(|(id, layer, pos, reason)| {
+			let leans_on_batch: bool = match reason {
+				PlacementRejection::NothingAdjacent =>
+					[(0, 1), (0, -1), (1, 0), (-1, 0)].iter().any(|offset| accepted.contains(&(*pos + *offset))),
+				PlacementRejection::NoSupport => accepted.contains(&(*pos + (0, -1))),
+				_ => false,
+			};
+			if leans_on_batch {
+				update_queue.push((*id, *layer, *pos, TileChangeCause::Player));
+				accepted.insert(*pos);
+			}
+			!leans_on_batch
+		});
+		if waiting.len() == before { break; }
+	}
+
+	// Partly blocked shapes are normal, so only say why when nothing could be edited
+	if accepted.is_empty() {
+	if let Some((_, _, pos, reason)) = waiting.first() {
+		rejections.write(PlacementRejected { pos: *pos, reason: *reason });
+	}}
+}
+
+fn show_placement_rejections(
+	time: Res<Time>,
+	mut rejections: MessageReader<PlacementRejected>,
+	mut text: Query<(&mut Text, &mut Visibility, &mut RejectionText)>,
+) {
+	if let Ok((mut text, mut visibility, mut rejection)) = text.single_mut() {
+		if let Some(rejected) = rejections.read().last() {
+			text.0 = format!("{} ({}, {})", rejected.reason.reason(), rejected.pos.0.x, rejected.pos.0.y);
+			*visibility = Visibility::Inherited;
+			rejection.timer.reset();
+		}
+		rejection.timer.tick(time.delta());
+		if rejection.timer.just_finished() {
+			*visibility = Visibility::Hidden;
+		}
+	}
+}
+
+type SoundId = usize;
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum SoundCategory {
+	Tiles,
+	Footsteps,
+}
+
+#[derive(Resource)]
+struct SoundSettings {
+	master: f32,
+	tiles: f32,
+	footsteps: f32,
+}
+
+impl Default for SoundSettings {
+	#[inline]
+	fn default() -> Self {
+		Self {
+			master: 1.0,
+			tiles: 0.8,
+			footsteps: 0.5,
+		}
+	}
+}
+
+impl SoundSettings {
+	#[inline]
+	fn volume(&self, category: SoundCategory) -> f32 {
+		self.master * match category {
+			SoundCategory::Tiles => self.tiles,
+			SoundCategory::Footsteps => self.footsteps,
+		}
+	}
+}
+
+#[derive(Default, Clone, Copy)]
+struct TileSounds {
+	placed: Option<SoundId>,
+	broken: Option<SoundId>,
+	step: Option<SoundId>,
+}
+
+impl TileSounds {
+	#[inline]
+	fn block() -> Self {
+		Self {
+			placed: Some(SoundRegistry::PLACE),
+			broken: Some(SoundRegistry::DECONSTRUCT),
+			step: Some(SoundRegistry::STEP),
+		}
+	}
+}
+
+#[derive(Resource)]
+struct SoundRegistry {
+	// Slots without a recording yet are None and play nothing
+	sounds: [(Option<Handle<AudioSource>>, SoundCategory); Self::SOUNDS],
+}
+
+impl SoundRegistry {
+	const DECONSTRUCT: SoundId = 0;
+	const PLACE: SoundId = 1;
+	const STEP: SoundId = 2;
+	const SOUNDS: SoundId = 3;
+
+	// Tiles and sounds are measured in pixels, so pan across roughly a screen's width
+	const SPATIAL_SCALE: f32 = 1.0 / 64.0;
+
+	#[inline]
+	fn new(sounds: [(Option<Handle<AudioSource>>, SoundCategory); Self::SOUNDS]) -> Self { Self { sounds: sounds } }
+
+	// Sounds that are missing or failed to load are skipped rather than treated as errors
+	fn play(
+		&self,
+		commands: &mut Commands,
+		asset_server: &AssetServer,
+		settings: &SoundSettings,
+		id: Option<SoundId>,
+		at: Vec2,
+	) {
+		if let Some((Some(handle), category)) = id.and_then(|id| self.sounds.get(id)) {
+			if asset_server.load_state(handle).is_failed() { return; }
+			commands.spawn((
+				AudioPlayer::new(handle.clone()),
+				PlaybackSettings::DESPAWN
+					.with_spatial(true)
+					.with_spatial_scale(SpatialScale::new_2d(Self::SPATIAL_SCALE))
+					.with_volume(Volume::Linear(settings.volume(*category))),
+				Transform::from_translation(at.extend(0.0)),
+			));
+		}
+	}
+}
+
+#[derive(Component, Default)]
+struct Footsteps {
+	distance: f32,
+}
+
+impl Footsteps {
+	const STEP_DISTANCE: f32 = 1.5;
+}
+
+fn play_tile_sounds(
+	mut commands: Commands,
+	asset_server: Res<AssetServer>,
+	registry: Res<SoundRegistry>,
+	settings: Res<SoundSettings>,
+	tile_ids: Res<TileIds>,
+	mut changed: MessageReader<TileChanged>,
+) {
+	// Bulk edits change many tiles at once, so each sound plays at most once a frame
+	let mut played: HashSet<SoundId> = HashSet::new();
+	for change in changed.read() {
+		if change.cause == TileChangeCause::Load { continue; }
+		let sound: Option<SoundId> = match change.new {
+			TileIds::AIR => tile_ids.by_id(change.old).sounds.broken,
+			_ => tile_ids.by_id(change.new).sounds.placed,
+		};
+		if let Some(id) = sound {
+		if played.insert(id) {
+			let at: Vec2 = Vec2::new(change.pos.0.x as f32 * 8.0, change.pos.0.y as f32 * 8.0);
+			registry.play(&mut commands, &asset_server, &settings, Some(id), at);
+		}}
+	}
+}
+
+fn play_footsteps(
+	mut commands: Commands,
+	time: Res<Time>,
+	asset_server: Res<AssetServer>,
+	registry: Res<SoundRegistry>,
+	settings: Res<SoundSettings>,
+	tile_ids: Res<TileIds>,
+	chunks: Query<&Chunk>,
+	tiles: Query<&Tile>,
+	mut mobs: Query<(&Mob, &mut Footsteps)>,
+) {
+	for (mob, mut footsteps) in &mut mobs {
+		if !mob.touching_grass || matches!(mob.walk_state, WalkState::None) {
+			footsteps.distance = 0.0;
+			continue;
+		}
+		footsteps.distance += mob.velocity.x.abs() * time.delta_secs();
+		if footsteps.distance >= Footsteps::STEP_DISTANCE {
+			footsteps.distance = 0.0;
+			let below: TileAbsolutePosition = TileAbsolutePosition::new(
+				mob.position.x.floor() as i64,
+				mob.position.y.floor() as i64 - 1
+			);
+			if let Some(id) = chunks.tile_id_at(&tiles, below, TileLayer::Foreground) {
+				registry.play(&mut commands, &asset_server, &settings, tile_ids.by_id(id).sounds.step, mob.position * 8.0);
+			}
+		}
+	}
+}
+
+const GRAVITY: f32 = 9.81;
+const PHYSICS_TICK_RATE: f64 = 60.0;
+
+// Files in settings/ hold one `name = value` pair per line, anything missing keeps its current value
+trait SettingsFile: Sized {
+	const FIELDS: usize;
+	// Used when warning about names the file has but the settings don't
+	const KIND: &'static str;
+
+	fn setting_mut(&mut self, index: usize) -> (&'static str, &mut f32);
+
+	fn read_over(mut self, path: std::path::PathBuf) -> std::io::Result<Self> {
+		for line in std::fs::read_to_string(path)?.lines() {
+			if let Some((name, value)) = line.split_once('=') {
+			if let Ok(value) = value.trim().parse::<f32>() {
+				match (0..Self::FIELDS).find(|index| self.setting_mut(*index).0 == name.trim()) {
+					Some(index) => *self.setting_mut(index).1 = value,
+					None => warn!("Unknown {} setting {}", Self::KIND, name.trim()),
+				}
+			}}
+		}
+		Ok(self)
+	}
+}
+
+#[derive(Resource, Clone, Copy, PartialEq)]
+struct PhysicsConfig {
+	gravity: f32,
+	jump_impulse: f32,
+	walk_acceleration: f32,
+	ground_friction: f32,
+	bounce_threshold: f32,
+	climb_speed: f32,
+	swim_gravity: f32,
+	swim_acceleration: f32,
+	tick_rate: f32,
+}
+
+impl Default for PhysicsConfig {
+	#[inline]
+	fn default() -> Self {
+		Self {
+			gravity: GRAVITY * 8.0,
+			jump_impulse: 30.0,
+			walk_acceleration: 5.0,
+			ground_friction: 10.0,
+			bounce_threshold: 4.0,
+			climb_speed: 6.0,
+			swim_gravity: 0.3,
+			swim_acceleration: 60.0,
+			tick_rate: PHYSICS_TICK_RATE as f32,
+		}
+	}
+}
+
+impl SettingsFile for PhysicsConfig {
+	const FIELDS: usize = 9;
+	const KIND: &'static str = "physics";
+
+	#[inline]
+	fn setting_mut(&mut self, index: usize) -> (&'static str, &mut f32) {
+		let (name, value, _) = self.field_mut(index);
+		(name, value)
+	}
+}
+
+impl PhysicsConfig {
+	// Name, value and how much one debug panel step changes it
+	fn field_mut(&mut self, index: usize) -> (&'static str, &mut f32, f32) {
+		match index {
+			0 => ("gravity", &mut self.gravity, 2.0),
+			1 => ("jump_impulse", &mut self.jump_impulse, 1.0),
+			2 => ("walk_acceleration", &mut self.walk_acceleration, 0.5),
+			3 => ("ground_friction", &mut self.ground_friction, 0.5),
+			4 => ("bounce_threshold", &mut self.bounce_threshold, 0.5),
+			5 => ("climb_speed", &mut self.climb_speed, 0.5),
+			6 => ("swim_gravity", &mut self.swim_gravity, 0.05),
+			7 => ("swim_acceleration", &mut self.swim_acceleration, 2.0),
+			_ => ("tick_rate", &mut self.tick_rate, 5.0),
+		}
+	}
+
+	fn path() -> std::path::PathBuf {
+		let mut path = std::env::current_dir().unwrap();
+		path.push("settings");
+		path.push("physics.cfg");
+		path
+	}
+
+	#[inline]
+	fn load() -> std::io::Result<Self> { Self::default().read_over(Self::path()) }
+
+	fn save(&self) -> std::io::Result<()> {
+		let path = Self::path();
+		if let Some(parent) = path.parent() {
+			std::fs::create_dir_all(parent)?;
+		}
+		let mut config: Self = *self;
+		let mut saving_to = std::fs::File::create(path)?;
+		for index in 0..Self::FIELDS {
+			let (name, value, _) = config.field_mut(index);
+			writeln!(saving_to, "{} = {}", name, value)?;
+		}
+		Ok(())
+	}
+}
+
+// Replaces the global PhysicsConfig for one mob
+#[derive(Component)]
+struct MobPhysics(PhysicsConfig);
+
+impl MobPhysics {
+	// settings/<name>.physics.cfg only needs the values that differ, the rest are taken from the global config when loaded
+	fn load(name: &str, global: &PhysicsConfig) -> std::io::Result<Self> {
+		let mut path = std::env::current_dir().unwrap();
+		path.push("settings");
+		path.push(format!("{}.physics.cfg", name));
+		Ok(Self { 0: global.read_over(path)? })
+	}
+}
+
+#[cfg(test)]
+mod physics_config_tests {
+	use super::*;
+	use bevy::ecs::system::RunSystemOnce;
+
+	#[test]
+	fn overrides_keep_unlisted_values() {
+		let path = std::env::temp_dir().join(format!("mob_override_{}.physics.cfg", std::process::id()));
+		std::fs::write(&path, "jump_impulse = 45\nnot_a_setting = 1\n").unwrap();
+		let global: PhysicsConfig = PhysicsConfig { gravity: 20.0, ..default() };
+		let config: PhysicsConfig = global.read_over(path.clone()).unwrap();
+		std::fs::remove_file(path).unwrap();
+
+		assert_eq!(config.jump_impulse, 45.0);
+		assert_eq!(config.gravity, 20.0);
+		assert_eq!(config.walk_acceleration, global.walk_acceleration);
+	}
+
+	#[test]
+	fn mob_physics_replaces_global_config() {
+		// do_physics steps mobs in parallel
+		bevy::tasks::ComputeTaskPool::get_or_init(bevy::tasks::TaskPool::default);
+		let mut world: World = World::new();
+		world.insert_resource(PhysicsConfig::default());
+		world.insert_resource(TileIds::new(core::array::from_fn(
+			|_| TileData::new(false, SmoothGroups::NONE, false, (Handle::default(), Handle::default()))
+		)));
+		let mut time: Time = Time::default();
+		time.advance_by(core::time::Duration::from_millis(100));
+		world.insert_resource(time);
+
+		let global = world.spawn(Mob::default()).id();
+		let weightless = world.spawn((
+			Mob::default(),
+			MobPhysics(PhysicsConfig { gravity: 0.0, ..default() }),
+		)).id();
+		world.run_system_once(do_physics).unwrap();
+
+		assert!(world.get::<Mob>(global).unwrap().velocity.y < 0.0);
+		assert_eq!(world.get::<Mob>(weightless).unwrap().velocity, Vec2::ZERO);
+	}
+}
+
+#[derive(Component)]
+struct PhysicsPanel {
+	selected: usize,
+}
+
+fn apply_physics_config(
+	config: Res<PhysicsConfig>,
+	mut fixed_time: ResMut<Time<Fixed>>,
+) {
+	if config.is_changed() {
+		fixed_time.set_timestep_hz(config.tick_rate.max(1.0) as f64);
+	}
+}
+
+fn physics_panel(
+	keys: Res<ButtonInput<KeyCode>>,
+	mut config: ResMut<PhysicsConfig>,
+	mut panel: Query<(&mut Text, &mut Visibility, &mut PhysicsPanel)>,
+) {
+	if let Ok((mut text, mut visibility, mut panel)) = panel.single_mut() {
+		if keys.just_pressed(KeyCode::F3) {
+			*visibility = match *visibility {
+				Visibility::Hidden => Visibility::Inherited,
+				_ => Visibility::Hidden,
+			};
+		}
+		if *visibility == Visibility::Hidden { return; }
+
+		if keys.just_pressed(KeyCode::ArrowUp) {
+			panel.selected = (panel.selected + PhysicsConfig::FIELDS - 1) % PhysicsConfig::FIELDS;
+		} else if keys.just_pressed(KeyCode::ArrowDown) {
+			panel.selected = (panel.selected + 1) % PhysicsConfig::FIELDS;
+		} else if keys.any_just_pressed([KeyCode::ArrowLeft, KeyCode::ArrowRight]) {
+			let (_, value, step) = config.field_mut(panel.selected);
+			*value = match keys.just_pressed(KeyCode::ArrowRight) {
+				true => *value + step,
+				false => (*value - step).max(0.0),
+			};
+		} else if keys.just_pressed(KeyCode::F5) {
+			if let Err(error) = config.save() {
+				warn!("Failed to save physics settings: {}", error);
+			}
+		} else if keys.just_pressed(KeyCode::F9) {
+			match PhysicsConfig::load() {
+				Ok(loaded) => *config = loaded,
+				Err(error) => warn!("Failed to load physics settings: {}", error),
+			}
+		}
+
+		let mut shown: PhysicsConfig = *config;
+		text.0 = (0..PhysicsConfig::FIELDS).map(|index| {
+			let (name, value, _) = shown.field_mut(index);
+			format!("{} {} = {:.2}\n", match index == panel.selected { true => ">", false => " " }, name, value)
+		}).collect();
+	}
+}
+
+// Small xorshift generator, for effects and simulation that don't need anything stronger
+#[derive(Clone, Copy)]
+struct XorShift(u64);
+
+impl XorShift {
+	#[inline]
+	fn new(seed: u64) -> Self { Self(seed.max(1)) }
+
+	#[inline]
+	fn next_u64(&mut self) -> u64 {
+		self.0 ^= self.0 << 13;
+		self.0 ^= self.0 >> 7;
+		self.0 ^= self.0 << 17;
+		self.0
+	}
+
+	// In the range [0, 1)
+	#[inline]
+	fn next_f32(&mut self) -> f32 { (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32 }
+
+	#[inline]
+	fn range_f32(&mut self, min: f32, max: f32) -> f32 { min + self.next_f32() * (max - min) }
+}
+
+#[derive(Component, Default)]
+struct Particle {
+	position: Vec2,
+	velocity: Vec2,
+	life: f32,
+}
+
+#[derive(Resource)]
+struct ParticlePool {
+	free: Vec<Entity>,
+	rng: XorShift,
+}
+
+impl ParticlePool {
+	const CAPACITY: usize = 256;
+	const BREAK_COUNT: usize = 8;
+	const PLACE_COUNT: usize = 3;
+	const FRAGMENT_SIZE: f32 = 2.0;
+
+	// Returns the particle entity and where it starts; nothing is spawned if the pool is used up
+	fn take(&mut self, around: TileAbsolutePosition) -> Option<(Entity, Vec2, Vec2)> {
+		let entity: Entity = self.free.pop()?;
+		Some((
+			entity,
+			Vec2::new(
+				around.0.x as f32 + self.rng.range_f32(-0.4, 0.4),
+				around.0.y as f32 + self.rng.range_f32(-0.4, 0.4),
+			),
+			Vec2::new(self.rng.range_f32(-6.0, 6.0), self.rng.range_f32(4.0, 14.0)),
+		))
+	}
+}
+
+fn spawn_tile_particles(
+	tile_ids: Res<TileIds>,
+	mut pool: ResMut<ParticlePool>,
+	mut changed: MessageReader<TileChanged>,
+	mut particles: Query<(&mut Particle, &mut Sprite, &mut Visibility)>,
+) {
+	for change in changed.read() {
+		if change.cause == TileChangeCause::Load { continue; }
+		let (from, count): (TileId, usize) = match change.new {
+			TileIds::AIR => (change.old, ParticlePool::BREAK_COUNT),
+			_ => (change.new, ParticlePool::PLACE_COUNT),
+		};
+		if from == TileIds::AIR || tile_ids.by_id(from).fluid.is_some() { continue; }
+
+		for _ in 0..count {
+			if let Some((entity, position, velocity)) = pool.take(change.pos) {
+			if let Ok((mut particle, mut sprite, mut visibility)) = particles.get_mut(entity) {
+				// Fragments show a random patch of the tile's first autotile frame
+				let corner: Vec2 = Vec2::new(pool.rng.range_f32(0.0, 10.0), pool.rng.range_f32(0.0, 10.0)).floor();
+				sprite.image = tile_ids.by_id(from).texture.1.clone();
+				sprite.rect = Some(Rect::from_corners(corner, corner + ParticlePool::FRAGMENT_SIZE));
+				particle.position = position;
+				particle.velocity = velocity;
+				particle.life = pool.rng.range_f32(0.4, 0.9);
+				*visibility = Visibility::Inherited;
+			}}
+		}
+	}
+}
+
+fn update_particles(
+	time: Res<Time>,
+	config: Res<PhysicsConfig>,
+	tile_ids: Res<TileIds>,
+	chunks: Query<&Chunk>,
+	tiles: Query<&Tile>,
+	mut pool: ResMut<ParticlePool>,
+	mut particles: Query<(Entity, &mut Particle, &mut Transform, &mut Visibility)>,
+) {
+	let is_solid = |at: Vec2| -> bool {
+		match chunks.tile_id_at(&tiles, TileAbsolutePosition::new(at.x.round() as i64, at.y.round() as i64), TileLayer::Foreground) {
+			Some(id) => tile_ids.by_id(id).solid,
+			None => false,
+		}
+	};
+
+	for (entity, mut particle, mut transfem, mut visibility) in &mut particles {
+		if particle.life <= 0.0 { continue; }
+		particle.life -= time.delta_secs();
+		if particle.life <= 0.0 {
+			*visibility = Visibility::Hidden;
+			pool.free.push(entity);
+			continue;
+		}
+
+		particle.velocity.y -= config.gravity * time.delta_secs();
+		let step: Vec2 = particle.velocity * time.delta_secs();
+		if is_solid(particle.position + Vec2::new(step.x, 0.0)) {
+			particle.velocity.x *= -0.3;
+		} else {
+			particle.position.x += step.x;
+		}
+		if is_solid(particle.position + Vec2::new(0.0, step.y)) {
+			particle.velocity.y *= -0.3;
+			particle.velocity.x *= 0.6;
+		} else {
+			particle.position.y += step.y;
+		}
+
+		transfem.translation.x = particle.position.x * 8.0;
+		transfem.translation.y = particle.position.y * 8.0;
+	}
+}
+
+// Fixed ticks in a full day, starting from midnight
+const DAY_LENGTH: u64 = 24000;
+
+#[derive(Resource)]
+struct WorldTime {
+	ticks: u64,
+	frozen: bool,
+	applied_sunlight: f32,
+}
+
+impl Default for WorldTime {
+	#[inline]
+	fn default() -> Self {
+		Self {
+			// New worlds start in the morning
+			ticks: DAY_LENGTH * 3 / 10,
+			frozen: false,
+			applied_sunlight: 0.0,
+		}
+	}
+}
+
+impl WorldTime {
+	// Where each part of the day peaks, as (time of day, sky colour, sunlight)
+	const KEYFRAMES: [(f32, [f32; 3], f32); 8] = [
+		(0.0, [0.02, 0.03, 0.08], 0.15),
+		(0.2, [0.02, 0.03, 0.08], 0.15),
+		(0.25, [0.8, 0.45, 0.3], 0.6),
+		(0.3, [0.3, 0.6, 0.6], 1.0),
+		(0.7, [0.3, 0.6, 0.6], 1.0),
+		(0.75, [0.75, 0.35, 0.35], 0.6),
+		(0.8, [0.02, 0.03, 0.08], 0.15),
+		(1.0, [0.02, 0.03, 0.08], 0.15),
+	];
+
+	// In the range [0, 1), 0.25 is dawn, 0.5 noon and 0.75 dusk
+	#[inline]
+	fn time_of_day(&self) -> f32 { (self.ticks % DAY_LENGTH) as f32 / DAY_LENGTH as f32 }
+
+	fn sample(&self) -> ([f32; 3], f32) {
+		let time: f32 = self.time_of_day();
+		for pair in Self::KEYFRAMES.windows(2) {
+			let ((start, start_sky, start_sun), (end, end_sky, end_sun)) = (pair[0], pair[1]);
+			if time >= start && time < end {
+				let blend: f32 = (time - start) / (end - start);
+				return (
+					core::array::from_fn(|i| start_sky[i] + (end_sky[i] - start_sky[i]) * blend),
+					start_sun + (end_sun - start_sun) * blend,
+				);
+			}
+		}
+		(Self::KEYFRAMES[0].1, Self::KEYFRAMES[0].2)
+	}
+
+	fn path() -> std::path::PathBuf {
+		let mut path = std::env::current_dir().unwrap();
+		path.push("save");
+		path.push("world.time");
+		path
+	}
+
+	fn load() -> std::io::Result<Self> {
+		let mut data: [u8; 8] = [0; 8];
+		std::fs::File::open(Self::path())?.read_exact(&mut data)?;
+		Ok(Self {
+			ticks: u64::from_be_bytes(data),
+			..default()
+		})
+	}
+
+	fn save(&self) -> std::io::Result<()> {
+		let path = Self::path();
+		if let Some(parent) = path.parent() {
+			std::fs::create_dir_all(parent)?;
+		}
+		std::fs::write(path, self.ticks.to_be_bytes())
+	}
+}
+
+fn advance_world_time(
+	mut world_time: ResMut<WorldTime>,
+) {
+	if !world_time.frozen {
+		world_time.ticks += 1;
+	}
+}
+
+// Sunlight only gets pushed to the LightMap once it has moved far enough, so the sky lit tiles aren't retinted every frame
+fn update_sky(
+	mut world_time: ResMut<WorldTime>,
+	mut clear_color: ResMut<ClearColor>,
+	mut light: ResMut<LightMap>,
+) {
+	let (sky, sunlight): ([f32; 3], f32) = world_time.sample();
+	clear_color.0 = Color::srgb(sky[0], sky[1], sky[2]);
+	if (sunlight - world_time.applied_sunlight).abs() < 0.02 { return; }
+	world_time.applied_sunlight = sunlight;
+	light.set_sunlight(sunlight);
+}
+
+// F6 freezes or resumes time, F7 skips ahead to the next dawn, noon, dusk or midnight
+fn world_time_input(
+	keys: Res<ButtonInput<KeyCode>>,
+	mut world_time: ResMut<WorldTime>,
+) {
+	if keys.just_pressed(KeyCode::F6) {
+		world_time.frozen = !world_time.frozen;
+		info!("World time {}", match world_time.frozen {
+			true => "frozen",
+			false => "resumed",
+		});
+	} else if keys.just_pressed(KeyCode::F7) {
+		let quarter: u64 = DAY_LENGTH / 4;
+		world_time.ticks = (world_time.ticks / quarter + 1) * quarter;
+		info!("World time set to {:.2} of the day", world_time.time_of_day());
+	}
+}
+
+const MAX_LIGHT: u8 = 15;
+// How bright a tile with no light at all still looks
+const AMBIENT_LIGHT: f32 = 0.08;
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum LightChannel {
+	Sky,
+	Block,
+}
+
+struct ChunkLight {
+	sky: Vec<u8>,
+	block: Vec<u8>,
+}
+
+// Sky and block light are kept apart so sunlight can be dimmed without relighting anything
+#[derive(Resource)]
+struct LightMap {
+	chunks: HashMap<ChunkPosition, ChunkLight>,
+	changed: HashSet<TileAbsolutePosition>,
+	sunlight: f32,
+}
+
+impl Default for LightMap {
+	#[inline]
+	fn default() -> Self {
+		Self {
+			chunks: HashMap::new(),
+			changed: HashSet::new(),
+			sunlight: 1.0,
+		}
+	}
+}
+
+impl LightMap {
+	#[inline]
+	fn get(&self, pos: TileAbsolutePosition, channel: LightChannel) -> Option<u8> {
+		let (chunk_pos, local_pos) = pos.to_positions();
+		self.chunks.get(&chunk_pos).map(|chunk| match channel {
+			LightChannel::Sky => chunk.sky[local_pos.to_flat()],
+			LightChannel::Block => chunk.block[local_pos.to_flat()],
+		})
+	}
+
+	#[inline]
+	fn set(&mut self, pos: TileAbsolutePosition, channel: LightChannel, level: u8) {
+		let (chunk_pos, local_pos) = pos.to_positions();
+		if let Some(chunk) = self.chunks.get_mut(&chunk_pos) {
+			match channel {
+				LightChannel::Sky => chunk.sky[local_pos.to_flat()] = level,
+				LightChannel::Block => chunk.block[local_pos.to_flat()] = level,
+			}
+			self.changed.insert(pos);
+		}
+	}
+
+	// Sunlight scales the sky channel as a whole, so only tiles lit more by the sky than by blocks need retinting
+	fn set_sunlight(&mut self, sunlight: f32) {
+		let old: f32 = self.sunlight;
+		self.sunlight = sunlight;
+		let mut changed: Vec<TileAbsolutePosition> = Vec::new();
+		for (chunk_pos, chunk) in &self.chunks {
+			for i in 0..Chunk::SIZE {
+				let (sky, block): (f32, f32) = (chunk.sky[i] as f32, chunk.block[i] as f32);
+				if (sky * old).max(block) != (sky * sunlight).max(block) {
+					changed.push((*chunk_pos, ChunkRelativePosition::from_flat(&(i as i64))).to_tile_absolute_position());
+				}
+			}
+		}
+		self.changed.extend(changed);
+	}
+
+	#[inline]
+	fn brightness(&self, pos: TileAbsolutePosition) -> f32 {
+		let sky: f32 = self.get(pos, LightChannel::Sky).unwrap_or(MAX_LIGHT) as f32 * self.sunlight;
+		let block: f32 = self.get(pos, LightChannel::Block).unwrap_or(0) as f32;
+		AMBIENT_LIGHT + (1.0 - AMBIENT_LIGHT) * sky.max(block) / MAX_LIGHT as f32
+	}
+
+	// Light lost stepping into a tile. Full sunlight falls straight down through clear tiles without fading.
+	#[inline]
+	fn cost(channel: LightChannel, from_level: u8, offset: (i64, i64), opacity: u8) -> u8 {
+		match channel == LightChannel::Sky && offset == (0, -1) && from_level == MAX_LIGHT && opacity == 0 {
+			true => 0,
+			false => 1 + opacity,
+		}
+	}
+
+	// Light a tile makes by itself: its emission, or sunlight if nothing loaded is above it
+	#[inline]
+	fn source(
+		channel: LightChannel,
+		pos: TileAbsolutePosition,
+		props: &impl Fn(TileAbsolutePosition) -> Option<(u8, u8)>,
+	) -> u8 {
+		match (channel, props(pos)) {
+			(_, None) => 0,
+			(LightChannel::Block, Some((_, emission))) => emission,
+			(LightChannel::Sky, Some((opacity, _))) => match props(pos + (0, 1)) {
+				None => MAX_LIGHT.saturating_sub(Self::cost(channel, MAX_LIGHT, (0, -1), opacity)),
+				Some(_) => 0,
+			},
+		}
+	}
+
+	// Removes the light that came through the changed tiles, then floods back in from whatever is still lit around them.
+	// props gives (opacity, emission) for loaded tiles.
+	fn relight(
+		&mut self,
+		channel: LightChannel,
+		changed: &[TileAbsolutePosition],
+		props: &impl Fn(TileAbsolutePosition) -> Option<(u8, u8)>,
+	) {
+		let mut removing: VecDeque<(TileAbsolutePosition, u8)> = VecDeque::new();
+		let mut spreading: VecDeque<TileAbsolutePosition> = VecDeque::new();
+		for pos in changed {
+			if let Some(old) = self.get(*pos, channel) {
+				self.set(*pos, channel, 0);
+				removing.push_back((*pos, old));
+			}
+		}
+
+		while let Some((pos, old)) = removing.pop_front() {
+			for offset in [(0, 1), (0, -1), (1, 0), (-1, 0)] {
+				let next: TileAbsolutePosition = pos + offset;
+				let level: u8 = match self.get(next, channel) {
+					Some(level) if level > 0 => level,
+					_ => continue,
+				};
+				if level < old || Self::cost(channel, old, offset, 0) == 0 && level == MAX_LIGHT {
+					self.set(next, channel, 0);
+					removing.push_back((next, level));
+					let source: u8 = Self::source(channel, next, props);
+					if source > 0 {
+						self.set(next, channel, source);
+						spreading.push_back(next);
+					}
+				} else {
+					spreading.push_back(next);
+				}
+			}
+		}
+
+		for pos in changed {
+			let source: u8 = Self::source(channel, *pos, props);
+			if source > self.get(*pos, channel).unwrap_or(MAX_LIGHT) {
+				self.set(*pos, channel, source);
+			}
+			spreading.push_back(*pos);
+			for offset in [(0, 1), (0, -1), (1, 0), (-1, 0)] {
+				spreading.push_back(*pos + offset);
+			}
+		}
+
+		while let Some(pos) = spreading.pop_front() {
+			let level: u8 = match self.get(pos, channel) {
+				Some(level) if level > 0 => level,
+				_ => continue,
+			};
+			for offset in [(0, 1), (0, -1), (1, 0), (-1, 0)] {
+				let next: TileAbsolutePosition = pos + offset;
+				if let Some((opacity, _)) = props(next) {
+				if let Some(current) = self.get(next, channel) {
+					let lit: u8 = level.saturating_sub(Self::cost(channel, level, offset, opacity));
+					if lit > current {
+						self.set(next, channel, lit);
+						spreading.push_back(next);
+					}
+				}}
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod light_tests {
+	use super::*;
+
+	const AIR: (u8, u8) = (0, 0);
+	const STONE: (u8, u8) = (4, 0);
+	const GLASS: (u8, u8) = (0, 0);
+	const LAMP: (u8, u8) = (4, 14);
+
+	// Chunks (0, 0) and (1, 0), with stone up to y = 20 and air above
+	fn ground() -> HashMap<TileAbsolutePosition, (u8, u8)> {
+		let mut tiles: HashMap<TileAbsolutePosition, (u8, u8)> = HashMap::new();
+		for x in 0..Chunk::WIDTH_I64 * 2 {
+			for y in 0..Chunk::HEIGHT_I64 {
+				tiles.insert(TileAbsolutePosition::new(x, y), match y <= 20 {
+					true => STONE,
+					false => AIR,
+				});
+			}
+		}
+		tiles
+	}
+
+	fn props(tiles: &HashMap<TileAbsolutePosition, (u8, u8)>) -> impl Fn(TileAbsolutePosition) -> Option<(u8, u8)> + '_ {
+		|pos| tiles.get(&pos).copied()
+	}
+
+	// Lights everything from nothing, the way update_lighting does for new chunks
+	fn full(tiles: &HashMap<TileAbsolutePosition, (u8, u8)>) -> LightMap {
+		let mut light: LightMap = LightMap::default();
+		for chunk in [ChunkPosition::new(0, 0), ChunkPosition::new(1, 0)] {
+			light.chunks.insert(chunk, ChunkLight { sky: vec![0; Chunk::SIZE], block: vec![0; Chunk::SIZE] });
+		}
+		let everything: Vec<TileAbsolutePosition> = tiles.keys().copied().collect();
+		light.relight(LightChannel::Sky, &everything, &props(tiles));
+		light.relight(LightChannel::Block, &everything, &props(tiles));
+		light
+	}
+
+	// Applies the edits one at a time and relights after each, then checks against lighting the result from scratch
+	fn assert_relights(mut tiles: HashMap<TileAbsolutePosition, (u8, u8)>, edits: &[((i64, i64), (u8, u8))]) {
+		let mut light: LightMap = full(&tiles);
+		for (at, tile) in edits {
+			let pos: TileAbsolutePosition = TileAbsolutePosition::new(at.0, at.1);
+			tiles.insert(pos, *tile);
+			light.relight(LightChannel::Sky, &[pos], &props(&tiles));
+			light.relight(LightChannel::Block, &[pos], &props(&tiles));
+		}
+		let expected: LightMap = full(&tiles);
+		for pos in tiles.keys() {
+			for channel in [LightChannel::Sky, LightChannel::Block] {
+				assert!(
+					light.get(*pos, channel) == expected.get(*pos, channel),
+					"{} light at ({}, {}) is {:?}, lighting from scratch gives {:?}",
+					match channel { LightChannel::Sky => "Sky", LightChannel::Block => "Block" },
+					pos.0.x, pos.0.y, light.get(*pos, channel), expected.get(*pos, channel),
+				);
+			}
+		}
+	}
+
+	// A room dug into the stone, roofed over so only block light reaches it
+	fn cave() -> HashMap<TileAbsolutePosition, (u8, u8)> {
+		let mut tiles = ground();
+		for x in 10..30 {
+			for y in 5..10 {
+				tiles.insert(TileAbsolutePosition::new(x, y), AIR);
+			}
+		}
+		tiles
+	}
+
+	#[test]
+	fn placing_and_removing_a_lamp() {
+		assert_relights(cave(), &[((20, 6), LAMP)]);
+		assert_relights(cave(), &[((20, 6), LAMP), ((20, 6), AIR)]);
+	}
+
+	#[test]
+	fn covering_and_uncovering_a_sky_column() {
+		assert_relights(ground(), &[((40, 40), STONE)]);
+		assert_relights(ground(), &[((40, 40), STONE), ((40, 40), AIR)]);
+		// Opening a shaft down into the cave lets sunlight in
+		assert_relights(cave(), &[((15, 20), AIR), ((15, 19), AIR), ((15, 18), AIR), ((15, 17), AIR), ((15, 16), AIR),
+			((15, 15), AIR), ((15, 14), AIR), ((15, 13), AIR), ((15, 12), AIR), ((15, 11), AIR), ((15, 10), AIR)]);
+	}
+
+	#[test]
+	fn glass_lets_sunlight_through() {
+		let mut tiles = ground();
+		tiles.insert(TileAbsolutePosition::new(40, 40), STONE);
+		assert_relights(tiles.clone(), &[((40, 40), GLASS)]);
+
+		tiles.insert(TileAbsolutePosition::new(40, 40), GLASS);
+		assert_eq!(full(&tiles).get(TileAbsolutePosition::new(40, 39), LightChannel::Sky), Some(MAX_LIGHT));
+	}
+
+	#[test]
+	fn sunlight_only_retints_sky_lit_tiles() {
+		let mut tiles = cave();
+		tiles.insert(TileAbsolutePosition::new(20, 6), LAMP);
+		tiles.insert(TileAbsolutePosition::new(40, 22), LAMP);
+		let mut light: LightMap = full(&tiles);
+		light.changed.clear();
+		light.set_sunlight(0.5);
+
+		assert!(light.changed.contains(&TileAbsolutePosition::new(60, 40)));
+		// Underground, and a lamp out in the open that outshines the sky
+		assert!(!light.changed.contains(&TileAbsolutePosition::new(21, 6)));
+		assert!(!light.changed.contains(&TileAbsolutePosition::new(15, 15)));
+		assert!(!light.changed.contains(&TileAbsolutePosition::new(40, 22)));
+		assert!(light.changed.iter().all(|pos| light.get(*pos, LightChannel::Sky) > Some(0)));
+	}
+
+	#[test]
+	fn light_crosses_chunk_borders() {
+		let mut tiles = ground();
+		for x in 50..80 {
+			for y in 5..10 {
+				tiles.insert(TileAbsolutePosition::new(x, y), AIR);
+			}
+		}
+		assert_relights(tiles.clone(), &[((Chunk::WIDTH_I64 - 1, 6), LAMP)]);
+		assert_relights(tiles.clone(), &[((Chunk::WIDTH_I64, 6), LAMP), ((Chunk::WIDTH_I64, 6), AIR)]);
+		assert!(full(&tiles).get(TileAbsolutePosition::new(Chunk::WIDTH_I64 + 1, 6), LightChannel::Block) == Some(0));
+		tiles.insert(TileAbsolutePosition::new(Chunk::WIDTH_I64 - 1, 6), LAMP);
+		assert!(full(&tiles).get(TileAbsolutePosition::new(Chunk::WIDTH_I64 + 1, 6), LightChannel::Block) == Some(12));
+	}
+}
+
+fn update_lighting(
+	mut light: ResMut<LightMap>,
+	mut changed: MessageReader<TileChanged>,
+	tile_ids: Res<TileIds>,
+	new_chunks: Query<&Chunk, Added<Chunk>>,
+	chunks: Query<&Chunk>,
+	tiles: Query<&Tile>,
+) {
+	let mut relighting: Vec<TileAbsolutePosition> = Vec::new();
+	for chunk in &new_chunks {
+		light.chunks.insert(chunk.pos, ChunkLight {
+			sky: vec![0; Chunk::SIZE],
+			block: vec![0; Chunk::SIZE],
+		});
+		for i in 0..Chunk::SIZE_I64 {
+			relighting.push((chunk.pos, ChunkRelativePosition::from_flat(&i)).to_tile_absolute_position());
+		}
+		// The row underneath isn't open to the sky anymore
+		let corner: TileAbsolutePosition = (chunk.pos, ChunkRelativePosition::new(0, 0)).to_tile_absolute_position();
+		for x in 0..Chunk::WIDTH_I64 {
+			relighting.push(corner + (x, -1));
+		}
+	}
+	for change in changed.read() {
+		if change.layer != TileLayer::Foreground { continue; }
+		let (old, new): (&TileData, &TileData) = (tile_ids.by_id(change.old), tile_ids.by_id(change.new));
+		if old.light_opacity != new.light_opacity || old.light_emission != new.light_emission {
+			relighting.push(change.pos);
+		}
+	}
+	if relighting.is_empty() { return; }
+
+	let props = |pos: TileAbsolutePosition| -> Option<(u8, u8)> {
+		chunks.tile_id_at(&tiles, pos, TileLayer::Foreground).map(
+			|id| (tile_ids.by_id(id).light_opacity, tile_ids.by_id(id).light_emission)
+		)
+	};
+	light.relight(LightChannel::Sky, &relighting, &props);
+	light.relight(LightChannel::Block, &relighting, &props);
+}
+
+fn tint_tiles(
+	mut light: ResMut<LightMap>,
+	chunks: Query<&Chunk>,
+	mut sprites: Query<&mut Sprite, With<Tile>>,
+) {
+	let changed: Vec<TileAbsolutePosition> = light.changed.drain().collect();
+	for pos in changed {
+		let brightness: f32 = light.brightness(pos);
+		if let Some(entities) = chunks.tile_at(pos) {
+		for entity in [entities.0, entities.1] {
+			if let Ok(mut sprite) = sprites.get_mut(entity) {
+				sprite.color = Color::srgb(brightness, brightness, brightness);
+			}
+		}}
+	}
+}
+
+// Random ticks land on this many positions in every loaded chunk each fixed tick
+const RANDOM_TICKS_PER_CHUNK: usize = 3;
+// Everything driven by random ticks repeats exactly for the same seed
+const WORLD_SEED: u64 = 0x2545F4914F6CDD1D;
+
+#[derive(Resource)]
+struct TickScheduler {
+	rng: XorShift,
+	random_ticks_per_chunk: usize,
+	tick: u64,
+	scheduled: BTreeMap<u64, Vec<(TileAbsolutePosition, TileLayer)>>,
+}
+
+impl TickScheduler {
+	#[inline]
+	fn new(seed: u64) -> Self {
+		Self {
+			rng: XorShift::new(seed),
+			random_ticks_per_chunk: RANDOM_TICKS_PER_CHUNK,
+			tick: 0,
+			scheduled: BTreeMap::new(),
+		}
+	}
+
+	#[inline]
+	fn schedule(&mut self, pos: TileAbsolutePosition, layer: TileLayer, in_ticks: u64) {
+		self.scheduled.entry(self.tick + in_ticks.max(1)).or_default().push((pos, layer));
+	}
+
+	// Ticks still waiting in a chunk, as (position, layer, ticks left)
+	fn scheduled_in(&self, chunk: ChunkPosition) -> Vec<(TileAbsolutePosition, TileLayer, u64)> {
+		self.scheduled.iter().flat_map(
+			|(due, positions)| positions.iter()
+				.filter(move |(pos, _)| pos.to_positions().0 == chunk)
+				.map(move |(pos, layer)| (*pos, *layer, due - self.tick))
+		).collect()
+	}
+
+	// Where this tick's random ticks land in a chunk
+	fn random_tick_positions(&mut self, chunk: ChunkPosition) -> Vec<TileAbsolutePosition> {
+		(0..self.random_ticks_per_chunk).map(|_| {
+			let local: ChunkRelativePosition = ChunkRelativePosition::from_flat(&((self.rng.next_u64() % Chunk::SIZE_U64) as i64));
+			(chunk, local).to_tile_absolute_position()
+		}).collect()
+	}
+
+	// Moves to the next tick and takes everything scheduled for it
+	fn advance(&mut self) -> Vec<(TileAbsolutePosition, TileLayer)> {
+		self.tick += 1;
+		let mut due: BTreeMap<u64, Vec<(TileAbsolutePosition, TileLayer)>> = self.scheduled.split_off(&(self.tick + 1));
+		std::mem::swap(&mut due, &mut self.scheduled);
+		due.into_values().flatten().collect()
+	}
+
+	fn clear_chunk(&mut self, chunk: ChunkPosition) {
+		for positions in self.scheduled.values_mut() {
+			positions.retain(|(pos, _)| pos.to_positions().0 != chunk);
+		}
+		self.scheduled.retain(|_, positions| !positions.is_empty());
+	}
+}
+
+fn run_ticks(
+	mut commands: Commands,
+	mut scheduler: ResMut<TickScheduler>,
+	tile_ids: Res<TileIds>,
+	chunks: Query<&Chunk>,
+	tiles: Query<&Tile>,
+) {
+	let due: Vec<(TileAbsolutePosition, TileLayer)> = scheduler.advance();
+
+	// Sorted so the same seed always ticks the same positions in the same order
+	let mut loaded: Vec<&Chunk> = chunks.iter().collect();
+	loaded.sort_by_key(|chunk| (chunk.pos.0.x, chunk.pos.0.y));
+	for chunk in loaded {
+		for pos in scheduler.random_tick_positions(chunk.pos) {
+			if let Some(entities) = chunk.at(pos.to_positions().1) {
+			for layer in [TileLayer::Background, TileLayer::Foreground] {
+				if let Ok(tile) = tiles.get(layer.select(entities)) {
+				if let Some(hook) = tile_ids.by_tile(tile).hooks.on_random_tick {
+					commands.run_system_with(hook, (pos, layer));
+				}}
+			}}
+		}
+	}
+
+	for (pos, layer) in due {
+		if let Some(id) = chunks.tile_id_at(&tiles, pos, layer) {
+		if let Some(hook) = tile_ids.by_id(id).hooks.on_scheduled_tick {
+			commands.run_system_with(hook, (pos, layer));
+		}}
+	}
+}
+
+#[cfg(test)]
+mod tick_tests {
+	use super::*;
+
+	#[test]
+	fn scheduled_ticks_survive_save_and_load() {
+		let chunk: ChunkPosition = ChunkPosition::new(-1, 2);
+		let pos: TileAbsolutePosition = (chunk, ChunkRelativePosition::new(3, 5)).to_tile_absolute_position();
+		let soon: TileAbsolutePosition = (chunk, ChunkRelativePosition::new(4, 5)).to_tile_absolute_position();
+		let mut scheduler: TickScheduler = TickScheduler::new(WORLD_SEED);
+		scheduler.schedule(pos, TileLayer::Foreground, 5);
+		scheduler.schedule(soon, TileLayer::Background, 1);
+		assert!(scheduler.advance() == vec![(soon, TileLayer::Background)]);
+		assert!(scheduler.advance().is_empty());
+
+		let mut saved: Vec<u8> = Vec::new();
+		write_scheduled_ticks(&mut saved, &scheduler, chunk).unwrap();
+		let mut loaded: TickScheduler = TickScheduler::new(WORLD_SEED);
+		for (tile_pos, layer, ticks_left) in read_scheduled_ticks(&mut saved.as_slice(), chunk).unwrap() {
+			loaded.schedule(tile_pos, layer, ticks_left);
+		}
+
+		assert!(loaded.advance().is_empty());
+		assert!(loaded.advance().is_empty());
+		assert!(loaded.advance() == vec![(pos, TileLayer::Foreground)]);
+		assert!(loaded.scheduled.is_empty());
+	}
+}
+
+// Chances per random tick
+#[derive(Resource, Clone, Copy)]
+struct GrassSettings {
+	spread_chance: f32,
+	decay_chance: f32,
+}
+
+impl Default for GrassSettings {
+	#[inline]
+	fn default() -> Self {
+		Self {
+			spread_chance: 0.5,
+			decay_chance: 1.0,
+		}
+	}
+}
+
+impl SettingsFile for GrassSettings {
+	const FIELDS: usize = 2;
+	const KIND: &'static str = "grass";
+
+	fn setting_mut(&mut self, index: usize) -> (&'static str, &mut f32) {
+		match index {
+			0 => ("spread_chance", &mut self.spread_chance),
+			_ => ("decay_chance", &mut self.decay_chance),
+		}
+	}
+}
+
+impl GrassSettings {
+	fn path() -> std::path::PathBuf {
+		let mut path = std::env::current_dir().unwrap();
+		path.push("settings");
+		path.push("grass.cfg");
+		path
+	}
+
+	#[inline]
+	fn load() -> std::io::Result<Self> { Self::default().read_over(Self::path()) }
+}
+
+fn grass_tick(
+	In((pos, layer)): In<(TileAbsolutePosition, TileLayer)>,
+	settings: Res<GrassSettings>,
+	mut scheduler: ResMut<TickScheduler>,
+	mut to_change: ResMut<TileChangeQueue>,
+	tile_ids: Res<TileIds>,
+	chunks: Query<&Chunk>,
+	tiles: Query<&Tile>,
+) {
+	if layer != TileLayer::Foreground { return; }
+	if let Some((id, target)) = grass_change(
+		pos,
+		&settings,
+		&mut scheduler.rng,
+		|at| chunks.tile_id_at(&tiles, at, TileLayer::Foreground),
+		|id| tile_ids.by_id(id).solid,
+	) {
+		to_change.push((id, TileLayer::Foreground, target, TileChangeCause::World));
+	}
+}
+
+// Grass dies under solid tiles, otherwise it tries to spread onto one random dirt tile around it that has air above
+fn grass_change(
+	pos: TileAbsolutePosition,
+	settings: &GrassSettings,
+	rng: &mut XorShift,
+	foreground: impl Fn(TileAbsolutePosition) -> Option<TileId>,
+	solid: impl Fn(TileId) -> bool,
+) -> Option<(TileId, TileAbsolutePosition)> {
+	let covered: bool = match foreground(pos + (0, 1)) {
+		Some(above) => solid(above),
+		None => false,
+	};
+	if covered {
+		return match rng.next_f32() < settings.decay_chance {
+			true => Some((TileIds::DIRT, pos)),
+			false => None,
+		};
+	}
+
+	if rng.next_f32() >= settings.spread_chance { return None; }
+	let offset: (i64, i64) = ((rng.next_u64() % 3) as i64 - 1, (rng.next_u64() % 3) as i64 - 1);
+	let target: TileAbsolutePosition = pos + offset;
+	match foreground(target) == Some(TileIds::DIRT) && foreground(target + (0, 1)) == Some(TileIds::AIR) {
+		true => Some((TileIds::GRASS, target)),
+		false => None,
+	}
+}
+
+#[cfg(test)]
+mod grass_tests {
+	use super::*;
+
+	// Random ticks over chunk (0, 0): a dirt ledge with grass at its left end, and a stone roof over a grass tile further along
+	fn grow(seed: u64, ticks: usize) -> Vec<(i64, i64)> {
+		let chunk: ChunkPosition = ChunkPosition::new(0, 0);
+		let mut world: HashMap<TileAbsolutePosition, TileId> = HashMap::new();
+		for x in 0..16 {
+			world.insert(TileAbsolutePosition::new(x, 10), TileIds::DIRT);
+			world.insert(TileAbsolutePosition::new(x + 16, 11), TileIds::DIRT);
+		}
+		world.insert(TileAbsolutePosition::new(0, 10), TileIds::GRASS);
+		world.insert(TileAbsolutePosition::new(24, 11), TileIds::GRASS);
+		world.insert(TileAbsolutePosition::new(24, 12), TileIds::STONE);
+
+		let mut scheduler: TickScheduler = TickScheduler::new(seed);
+		scheduler.random_ticks_per_chunk = 1024;
+		let settings: GrassSettings = GrassSettings::default();
+		for _ in 0..ticks {
+			// Changes only land once the tick is over, like the TileChangeQueue
+			let mut changes: Vec<(TileId, TileAbsolutePosition)> = Vec::new();
+			for pos in scheduler.random_tick_positions(chunk) {
+				if world.get(&pos) != Some(&TileIds::GRASS) { continue; }
+				changes.extend(grass_change(
+					pos,
+					&settings,
+					&mut scheduler.rng,
+					|at| Some(world.get(&at).copied().unwrap_or(TileIds::AIR)),
+					|id| id == TileIds::STONE,
+				));
+			}
+			for (id, pos) in changes {
+				world.insert(pos, id);
+			}
+		}
+
+		let mut grass: Vec<(i64, i64)> = world.iter()
+			.filter(|(_, id)| **id == TileIds::GRASS)
+			.map(|(pos, _)| (pos.0.x, pos.0.y))
+			.collect();
+		grass.sort();
+		grass
+	}
+
+	#[test]
+	fn same_seed_grows_the_same_way() {
+		assert_eq!(grow(WORLD_SEED, 200), grow(WORLD_SEED, 200));
+	}
+
+	// Grass at the origin, with every kind of neighbour around it
+	fn surroundings(at: TileAbsolutePosition) -> Option<TileId> {
+		Some(match (at.0.x, at.0.y) {
+			(0, 0) => TileIds::GRASS,
+			(1, 0) => TileIds::DIRT,// air above
+			(-1, 0) | (0, -1) | (-1, -1) => TileIds::DIRT,// something above
+			(-1, 1) | (1, -1) => TileIds::STONE,
+			_ => TileIds::AIR,
+		})
+	}
+
+	#[test]
+	fn spreads_only_onto_dirt_with_air_above() {
+		let settings: GrassSettings = GrassSettings { spread_chance: 1.0, decay_chance: 1.0 };
+		let mut rng: XorShift = XorShift::new(WORLD_SEED);
+		let targets: HashSet<(i64, i64)> = (0..500)
+			.filter_map(|_| grass_change(TileAbsolutePosition::new(0, 0), &settings, &mut rng, surroundings, |id| id == TileIds::STONE))
+			.map(|(id, pos)| {
+				assert_eq!(id, TileIds::GRASS);
+				(pos.0.x, pos.0.y)
+			})
+			.collect();
+		assert_eq!(targets, HashSet::from([(1, 0)]));
+	}
+
+	#[test]
+	fn covered_grass_turns_back_into_dirt() {
+		let roofed = |at: TileAbsolutePosition| match (at.0.x, at.0.y) {
+			(0, 1) => Some(TileIds::STONE),
+			_ => surroundings(at),
+		};
+		let settings: GrassSettings = GrassSettings { spread_chance: 1.0, decay_chance: 1.0 };
+		let mut rng: XorShift = XorShift::new(WORLD_SEED);
+		for _ in 0..10 {
+			assert!(grass_change(TileAbsolutePosition::new(0, 0), &settings, &mut rng, roofed, |id| id == TileIds::STONE) ==
+				Some((TileIds::DIRT, TileAbsolutePosition::new(0, 0))));
+		}
+		// Tiles that aren't solid don't cover it
+		assert!(grass_change(TileAbsolutePosition::new(0, 0), &settings, &mut rng, roofed, |_| false) != Some((TileIds::DIRT, TileAbsolutePosition::new(0, 0))));
+	}
+}
+
+#[derive(Copy, Clone)]
+struct FluidData {
+	// Fixed ticks between flow steps, thicker fluids flow less often
+	flow_interval: u32,
+	drag: f32,
+}
+
+// Chunks further than this from the player keep their fluids frozen
+const ACTIVE_CHUNK_RADIUS: i64 = 1;
+
+// Levels are the authority on where fluid is, tile ids catch up through the TileChangeQueue
+#[derive(Resource, Default)]
+struct FluidLevels {
+	levels: HashMap<TileAbsolutePosition, (TileId, u8)>,
+	active: HashSet<TileAbsolutePosition>,
+	tick: u32,
+}
+
+impl FluidLevels {
+	const MAX: u8 = 8;
+
+	#[inline]
+	fn level(&self, pos: TileAbsolutePosition) -> u8 {
+		match self.levels.get(&pos) {
+			Some((_, level)) => *level,
+			None => 0,
+		}
+	}
+
+	#[inline]
+	fn wake(&mut self, pos: TileAbsolutePosition) {
+		self.active.insert(pos);
+		for offset in [(0, 1), (0, -1), (1, 0), (-1, 0)] {
+			self.active.insert(pos + offset);
+		}
+	}
+
+	#[inline]
+	fn set(&mut self, pos: TileAbsolutePosition, id: TileId, level: u8) {
+		match level {
+			0 => self.levels.remove(&pos),
+			_ => self.levels.insert(pos, (id, level.min(Self::MAX))),
+		};
+		self.wake(pos);
+	}
+}
+
+#[inline]
+fn chunk_is_active(chunk: ChunkPosition, center: ChunkPosition) -> bool {
+	(chunk.0.x - center.0.x).abs() <= ACTIVE_CHUNK_RADIUS && (chunk.0.y - center.0.y).abs() <= ACTIVE_CHUNK_RADIUS
+}
+
+// Keeps fluid levels in step with edits that didn't come from the simulation
+fn wake_fluids(
+	tile_ids: Res<TileIds>,
+	mut fluids: ResMut<FluidLevels>,
+	mut changed: MessageReader<TileChanged>,
+) {
+	for change in changed.read() {
+		if change.layer != TileLayer::Foreground { continue; }
+		if tile_ids.by_id(change.new).fluid.is_some() {
+			if change.cause != TileChangeCause::World && !fluids.levels.contains_key(&change.pos) {
+				fluids.set(change.pos, change.new, FluidLevels::MAX);
+			}
+		} else if tile_ids.by_id(change.old).fluid.is_some() {
+			// Flowing only drains cells to air once their level has already moved, and it may have refilled them since
+			if change.cause != TileChangeCause::World || change.new != TileIds::AIR {
+				fluids.set(change.pos, change.old, 0);
+			}
+		}
+		fluids.wake(change.pos);
+	}
+}
+
+// Each step fluid falls as far as the cell below can hold, then levels out with its sides.
+// Cells that don't move drop out of the active set until something next to them changes.
+fn flow_fluids(
+	tile_ids: Res<TileIds>,
+	mut fluids: ResMut<FluidLevels>,
+	mut to_change: ResMut<TileChangeQueue>,
+	mut dirty: ResMut<DirtyTiles>,
+	chunks: Query<&Chunk>,
+	tiles: Query<&Tile>,
+	player: Query<&Mob, With<Player>>,
+) {
+	let center: ChunkPosition = match player.single() {
+		Ok(mob) => TileAbsolutePosition::new(mob.position.x.floor() as i64, mob.position.y.floor() as i64).to_positions().0,
+		Err(_) => return,
+	};
+	fluids.tick = fluids.tick.wrapping_add(1);
+	let tick: u32 = fluids.tick;
+
+	let mut waiting: Vec<TileAbsolutePosition> = fluids.active.drain().collect();
+	waiting.retain(|pos| fluids.levels.contains_key(pos));
+	waiting.sort_by_key(|pos| (pos.0.y, pos.0.x));
+	let mut before: HashMap<TileAbsolutePosition, TileId> = HashMap::new();
+	let mut still_active: Vec<TileAbsolutePosition> = Vec::new();
+
+	for pos in waiting {
+		let (id, mut level): (TileId, u8) = match fluids.levels.get(&pos) {
+			Some(found) => *found,
+			None => continue,
+		};
+		let fluid: FluidData = match tile_ids.by_id(id).fluid {
+			Some(fluid) => fluid,
+			None => {
+				fluids.levels.remove(&pos);
+				continue;
+			},
+		};
+		if !chunk_is_active(pos.to_positions().0, center) || !tick.is_multiple_of(fluid.flow_interval.max(1)) {
+			still_active.push(pos);
+			continue;
+		}
+
+		// How much more fluid a cell can take, None if it's blocked
+		let space = |fluids: &FluidLevels, at: TileAbsolutePosition| -> Option<u8> {
+			match fluids.levels.get(&at) {
+				Some((other, other_level)) if *other == id => Some(FluidLevels::MAX - other_level),
+				Some(_) => None,
+				None => match chunks.tile_id_at(&tiles, at, TileLayer::Foreground) {
+					Some(TileIds::AIR) => Some(FluidLevels::MAX),
+					_ => None,
+				},
+			}
+		};
+		let mut flows: Vec<(TileAbsolutePosition, u8)> = Vec::new();
+
+		if let Some(room) = space(&fluids, pos + (0, -1)) {
+			let flow: u8 = level.min(room);
+			if flow > 0 {
+				flows.push((pos + (0, -1), flow));
+				level -= flow;
+			}
+		}
+		let sides: [(i64, i64); 2] = match tick / fluid.flow_interval.max(1) % 2 {
+			0 => [(-1, 0), (1, 0)],
+			_ => [(1, 0), (-1, 0)],
+		};
+		for side in sides {
+			if let Some(room) = space(&fluids, pos + side) {
+				let beside: u8 = FluidLevels::MAX - room;
+				if level > beside + 1 {
+					let flow: u8 = (level - beside) / 2;
+					flows.push((pos + side, flow));
+					level -= flow;
+				}
+			}
+		}
+		if flows.is_empty() { continue; }
+
+		for (into, flow) in flows {
+			before.entry(into).or_insert(match fluids.levels.contains_key(&into) {
+				true => id,
+				false => TileIds::AIR,
+			});
+			let filled: u8 = fluids.level(into) + flow;
+			fluids.set(into, id, filled);
+		}
+		before.entry(pos).or_insert(id);
+		fluids.set(pos, id, level);
+	}
+	fluids.active.extend(still_active);
+
+	for (pos, was) in before {
+		let now: TileId = match fluids.levels.get(&pos) {
+			Some((id, _)) => *id,
+			None => TileIds::AIR,
+		};
+		if now != was {
+			to_change.push((now, TileLayer::Foreground, pos, TileChangeCause::World));
+		}
+		dirty.tiles.insert(pos);
+	}
+}
+
+fn do_physics(
+	time: Res<Time>,
+	config: Res<PhysicsConfig>,
+	tile_ids: Res<TileIds>,
+	mut mob_query: Query<(&mut Mob, Option<&MobPhysics>)>,
+	chunks: Query<&Chunk>,
+	blocks: Query<&Tile>,
+) {
+	let delta: f32 = time.delta_secs();
+	mob_query.par_iter_mut().for_each(|(mut mob, overridden)| {
+		let config: &PhysicsConfig = match overridden {
+			Some(MobPhysics(mob_config)) => mob_config,
+			None => &config,
+		};
+		step_mob(&mut mob, delta, config, &tile_ids, &chunks, &blocks);
+	});
+}
+
+fn step_mob(
+	mob: &mut Mob,
+	delta: f32,
+	config: &PhysicsConfig,
+	tile_ids: &TileIds,
+	chunks: &Query<&Chunk>,
+	blocks: &Query<&Tile>,
+) {
+	let on_climbable: bool = cells_between(mob.position, mob.position + mob.size).any(
+		|pos| match chunks.tile_id_at(blocks, pos, TileLayer::Foreground) {
+			Some(id) => tile_ids.by_id(id).climbable,
+			None => false,
+		}
+	);
+	let fluid: Option<FluidData> = cells_between(mob.position, mob.position + mob.size).find_map(
+		|pos| chunks.tile_id_at(blocks, pos, TileLayer::Foreground).and_then(|id| tile_ids.by_id(id).fluid)
+	);
+	let was_climbing: bool = mob.climb_state == ClimbState::Climbing;
+	mob.climb_state = match (mob.climb_state, mob.climb_direction, mob.jump_state) {
+		_ if !on_climbable => ClimbState::None,
+		(ClimbState::Climbing, _, JumpState::TryJump) => ClimbState::None,
+		(ClimbState::None, ClimbDirection::None, _) => ClimbState::None,
+		_ => ClimbState::Climbing,
+	};
+
+	let mut new_velocity = mob.velocity;
+	if mob.climb_state == ClimbState::Climbing {
+		// Gravity is suspended while climbing
+		new_velocity = Vec2::new(mob.walk_state as i32 as f32, mob.climb_direction as i32 as f32) * config.climb_speed;
+	} else if let Some(fluid) = fluid {
+		// Swimming, holding jump pushes the mob up against the weakened gravity
+		new_velocity += Vec2::new(mob.walk_state as i32 as f32 * config.walk_acceleration * 0.5, 0.0);
+		new_velocity.y += match mob.jump_state {
+			JumpState::TryJump => config.swim_acceleration * delta,
+			_ => 0.0,
+		} - config.gravity * config.swim_gravity * delta;
+		new_velocity -= new_velocity * (fluid.drag * delta).min(1.0);
+	} else {
+		let can_jump: bool = mob.touching_grass || (was_climbing && on_climbable);
+		new_velocity += if can_jump {(
+			match mob.jump_state {
+				JumpState::TryJump => Vec2::new(0.0, config.jump_impulse),
+				_ => Vec2::ZERO,
+			} +
+			match mob.walk_state {
+				WalkState::None => Vec2::ZERO,
+				_ => Vec2::new(mob.walk_state as i32 as f32 * config.walk_acceleration, 0.0),
+			}
+		)} else {
+			Vec2::ZERO
+		} + Vec2::new(0.0, -config.gravity * delta);
+	}
+	mob.previous_position = mob.position;
+	let platform_floor: Option<f32> = match mob.drop_through || new_velocity.y > 0.0 {
+		true => None,
+		false => Some(mob.previous_position.y),
+	};
+	let (new_loc, contacts): (Vec2, Contacts) = sweep_aabb(mob.position, mob.size, new_velocity * delta, platform_floor,
+		|pos| match chunks.tile_id_at(blocks, pos, TileLayer::Foreground) {
+			Some(id) => tile_ids.by_id(id).shape,
+			None => CollisionShape::None,
+		}
+	);
+
+	let floor: Option<&TileData> = contacts.floor_tile
+		.and_then(|pos| chunks.tile_id_at(blocks, pos, TileLayer::Foreground))
+		.map(|id| tile_ids.by_id(id));
+	let impact: f32 = -new_velocity.y;
+
+	if contacts.floor || contacts.ceiling {
+		new_velocity.y = 0.0;
+	}
+	if contacts.wall_left || contacts.wall_right {
+		new_velocity.x = 0.0;
+	}
+	if let Some(floor) = floor {
+		new_velocity.x -= new_velocity.x * config.ground_friction * floor.friction * delta;
+		if impact > config.bounce_threshold {
+			new_velocity.y = impact * floor.bounciness;
+		}
+	}
+
+	mob.touching_grass = contacts.floor;
+	mob.contacts = contacts;
+	mob.position = new_loc;
+	mob.velocity = new_velocity;
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
+struct Contacts {
+	floor: bool,
+	ceiling: bool,
+	wall_left: bool,
+	wall_right: bool,
+	floor_tile: Option<TileAbsolutePosition>,
+}
+
+// Longest distance a box can move in one step, so a huge delta can't make the sweep loop forever
+const MAX_SWEEP: f32 = 64.0;
+const SWEEP_EPSILON: f32 = 0.0001;
+// How far a mob can be lifted onto a slope in one step
+const SLOPE_STEP: f32 = 1.0;
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum CollisionShape {
+	None,
+	Full,
+	HalfBottom,
+	HalfTop,
+	Pane,
+	// Named after the side that is full height
+	SlopeRight,
+	SlopeLeft,
+	// Only blocks boxes falling onto it from above
+	Platform,
+}
+
+impl CollisionShape {
+	// The solid part of the cell, as offsets from its bottom left corner, for a box moving in the given direction.
+	// Slopes only block from below and from their tall side, their surface is handled by slope_height.
+	#[inline]
+	fn bounds(&self, moving: Vec2) -> Option<(Vec2, Vec2)> {
+		match self {
+			CollisionShape::None => None,
+			CollisionShape::Full => Some((Vec2::ZERO, Vec2::ONE)),
+			CollisionShape::HalfBottom => Some((Vec2::ZERO, Vec2::new(1.0, 0.5))),
+			CollisionShape::HalfTop => Some((Vec2::new(0.0, 0.5), Vec2::ONE)),
+			CollisionShape::Pane => Some((Vec2::new(0.375, 0.0), Vec2::new(0.625, 1.0))),
+			CollisionShape::SlopeRight => match moving.x < 0.0 || moving.y > 0.0 {
+				true => Some((Vec2::ZERO, Vec2::ONE)),
+				false => None,
+			},
+			CollisionShape::SlopeLeft => match moving.x > 0.0 || moving.y > 0.0 {
+				true => Some((Vec2::ZERO, Vec2::ONE)),
+				false => None,
+			},
+			CollisionShape::Platform => match moving.y < 0.0 {
+				true => Some((Vec2::new(0.0, 0.75), Vec2::ONE)),
+				false => None,
+			},
+		}
+	}
+
+	// Height of the slope under a box spanning [left, right], both relative to the cell
+	#[inline]
+	fn slope_height(&self, left: f32, right: f32) -> Option<f32> {
+		match self {
+			CollisionShape::SlopeRight => Some(right.clamp(0.0, 1.0)),
+			CollisionShape::SlopeLeft => Some((1.0 - left).clamp(0.0, 1.0)),
+			_ => None,
+		}
+	}
+}
+
+// Every cell overlapping the area between min and max
+#[inline]
+fn cells_between(min: Vec2, max: Vec2) -> impl Iterator<Item = TileAbsolutePosition> {
+	(min.y.floor() as i64..=max.y.ceil() as i64 - 1).flat_map(
+		move |row| (min.x.floor() as i64..=max.x.ceil() as i64 - 1).map(move |column| TileAbsolutePosition::new(column, row))
+	)
+}
+
+// Tile (x, y) fills [x, x + 1] on each axis and the box fills [position, position + size].
+// The box moves along x first, then y, and stops flush against the first solid part of a cell it would enter on each axis.
+// Anything the box already overlaps is ignored so it can't get stuck inside a tile placed on top of it.
+// Platforms only catch a box whose bottom was at or above their top on the previous tick, and none at all when platform_floor is None.
+fn sweep_aabb(
+	position: Vec2,
+	size: Vec2,
+	motion: Vec2,
+	platform_floor: Option<f32>,
+	shape_at: impl Fn(TileAbsolutePosition) -> CollisionShape,
+) -> (Vec2, Contacts) {
+	let motion: Vec2 = motion.clamp(Vec2::splat(-MAX_SWEEP), Vec2::splat(MAX_SWEEP));
+	let mut contacts: Contacts = Contacts::default();
+	let mut position: Vec2 = position;
+	let cell_origin = |cell: TileAbsolutePosition| Vec2::new(cell.0.x as f32, cell.0.y as f32);
+
+	if motion.x != 0.0 {
+		let mut allowed: f32 = motion.x;
+		for cell in cells_between(
+			Vec2::new(position.x + motion.x.min(0.0), position.y),
+			Vec2::new(position.x + size.x + motion.x.max(0.0), position.y + size.y)
+		) {
+			if let Some((min, max)) = shape_at(cell).bounds(Vec2::new(motion.x, 0.0)) {
+				let (min, max): (Vec2, Vec2) = (cell_origin(cell) + min, cell_origin(cell) + max);
+				if max.y <= position.y + SWEEP_EPSILON || min.y >= position.y + size.y - SWEEP_EPSILON { continue; }
+				if motion.x > 0.0 && min.x >= position.x + size.x - SWEEP_EPSILON {
+					allowed = allowed.min((min.x - position.x - size.x).max(0.0));
+				} else if motion.x < 0.0 && max.x <= position.x + SWEEP_EPSILON {
+					allowed = allowed.max((max.x - position.x).min(0.0));
+				}
+			}
+		}
+		if allowed != motion.x {
+			match motion.x > 0.0 {
+				true => contacts.wall_right = true,
+				false => contacts.wall_left = true,
+			}
+		}
+		position.x += allowed;
+	}
+
+	let slope_surface = |cell: TileAbsolutePosition, position: Vec2| -> Option<f32> {
+		shape_at(cell).slope_height(position.x - cell_origin(cell).x, position.x + size.x - cell_origin(cell).x)
+			.map(|height| cell_origin(cell).y + height)
+	};
+
+	// Walking into a slope lifts the box onto its surface instead of stopping it
+	if motion.y <= 0.0 {
+		for cell in cells_between(position, Vec2::new(position.x + size.x, position.y + SLOPE_STEP)) {
+			if let Some(surface) = slope_surface(cell, position) {
+			if surface > position.y && surface <= position.y + SLOPE_STEP {
+				position.y = surface;
+				contacts.floor = true;
+				contacts.floor_tile = Some(cell);
+			}}
+		}
+	}
+
+	if motion.y != 0.0 {
+		let mut allowed: f32 = motion.y;
+		let mut landed_on: Option<TileAbsolutePosition> = None;
+		for cell in cells_between(
+			Vec2::new(position.x, position.y + motion.y.min(0.0)),
+			Vec2::new(position.x + size.x, position.y + size.y + motion.y.max(0.0))
+		) {
+			let shape: CollisionShape = shape_at(cell);
+			if let Some((min, max)) = shape.bounds(Vec2::new(0.0, motion.y)) {
+				let (min, max): (Vec2, Vec2) = (cell_origin(cell) + min, cell_origin(cell) + max);
+				if max.x <= position.x + SWEEP_EPSILON || min.x >= position.x + size.x - SWEEP_EPSILON { continue; }
+				if shape == CollisionShape::Platform && !platform_floor.is_some_and(|floor| floor >= max.y - SWEEP_EPSILON) { continue; }
+				if motion.y > 0.0 && min.y >= position.y + size.y - SWEEP_EPSILON {
+					allowed = allowed.min((min.y - position.y - size.y).max(0.0));
+				} else if motion.y < 0.0 && max.y <= position.y + SWEEP_EPSILON && max.y - position.y > allowed {
+					allowed = (max.y - position.y).min(0.0);
+					landed_on = Some(cell);
+				}
+			}
+			if motion.y < 0.0 {
+			if let Some(surface) = slope_surface(cell, position) {
+			if surface <= position.y + SWEEP_EPSILON && surface - position.y > allowed {
+				allowed = (surface - position.y).min(0.0);
+				landed_on = Some(cell);
+			}}}
+		}
+		if allowed != motion.y {
+			match motion.y > 0.0 {
+				true => contacts.ceiling = true,
+				false => {
+					contacts.floor = true;
+					contacts.floor_tile = landed_on;
+				},
+			}
+		}
+		position.y += allowed;
+	}
+
+	(position, contacts)
+}
+
+#[cfg(test)]
+mod sweep_tests {
+	use super::*;
+
+	const MOB_SIZE: Vec2 = Vec2::new(1.0, 3.0);
+
+	fn solid(cells: &[(i64, i64)]) -> impl Fn(TileAbsolutePosition) -> CollisionShape {
+		let cells: HashSet<TileAbsolutePosition> = cells.iter().map(|(x, y)| TileAbsolutePosition::new(*x, *y)).collect();
+		move |pos| match cells.contains(&pos) {
+			true => CollisionShape::Full,
+			false => CollisionShape::None,
+		}
+	}
+
+	#[test]
+	fn lands_on_floor() {
+		let (position, contacts) = sweep_aabb(Vec2::new(0.0, 1.25), MOB_SIZE, Vec2::new(0.0, -0.5), None, solid(&[(-1, 0), (0, 0), (1, 0)]));
+		assert_eq!(position, Vec2::new(0.0, 1.0));
+		assert!(contacts.floor);
+		assert!(contacts.floor_tile == Some(TileAbsolutePosition::new(0, 0)));
+		assert!(!contacts.ceiling && !contacts.wall_left && !contacts.wall_right);
+	}
+
+	#[test]
+	fn stops_at_walls() {
+		let wall = solid(&[(-2, 1), (-2, 2), (-2, 3), (2, 1), (2, 2), (2, 3)]);
+		let (position, contacts) = sweep_aabb(Vec2::new(0.75, 1.0), MOB_SIZE, Vec2::new(0.5, 0.0), None, &wall);
+		assert_eq!(position, Vec2::new(1.0, 1.0));
+		assert!(contacts.wall_right && !contacts.wall_left);
+
+		let (position, contacts) = sweep_aabb(Vec2::new(-0.75, 1.0), MOB_SIZE, Vec2::new(-0.5, 0.0), None, &wall);
+		assert_eq!(position, Vec2::new(-1.0, 1.0));
+		assert!(contacts.wall_left && !contacts.wall_right);
+		assert!(!contacts.floor && !contacts.ceiling);
+	}
+
+	#[test]
+	fn ceiling_is_not_a_floor() {
+		let (position, contacts) = sweep_aabb(Vec2::new(0.0, 1.5), MOB_SIZE, Vec2::new(0.0, 1.0), None, solid(&[(0, 0), (0, 5)]));
+		assert_eq!(position, Vec2::new(0.0, 2.0));
+		assert!(contacts.ceiling);
+		assert!(!contacts.floor);
+		assert!(contacts.floor_tile.is_none());
+	}
+
+	#[test]
+	fn fast_mob_cannot_pass_through_thin_wall() {
+		let wall = solid(&[(3, 1), (3, 2), (3, 3)]);
+		for speed in [10.0, 40.0, 1000.0] {
+			let (position, contacts) = sweep_aabb(Vec2::new(0.0, 1.0), MOB_SIZE, Vec2::new(speed, 0.0), None, &wall);
+			assert_eq!(position, Vec2::new(2.0, 1.0));
+			assert!(contacts.wall_right);
+		}
+		let floor = solid(&[(0, -20)]);
+		let (position, contacts) = sweep_aabb(Vec2::new(0.0, 1.0), MOB_SIZE, Vec2::new(0.0, -50.0), None, &floor);
+		assert_eq!(position, Vec2::new(0.0, -19.0));
+		assert!(contacts.floor);
+	}
+
+	// Ground along y = 0, a slope at (slope, 1) rising towards a full tile at (top, 1)
+	fn slope_course(slope: i64, top: i64, shape: CollisionShape) -> impl Fn(TileAbsolutePosition) -> CollisionShape {
+		move |pos| match (pos.0.x, pos.0.y) {
+			(_, 0) => CollisionShape::Full,
+			(x, 1) if x == slope => shape,
+			(x, 1) if x == top => CollisionShape::Full,
+			_ => CollisionShape::None,
+		}
+	}
+
+	fn walk(start: Vec2, step: f32, ticks: usize, shape_at: impl Fn(TileAbsolutePosition) -> CollisionShape) -> Vec<Vec2> {
+		let mut position: Vec2 = start;
+		(0..ticks).map(|_| {
+			let (moved, contacts) = sweep_aabb(position, MOB_SIZE, Vec2::new(step, -0.125), Some(position.y), &shape_at);
+			assert!(contacts.floor);
+			position = moved;
+			position
+		}).collect()
+	}
+
+	#[test]
+	fn steps_up_slope_right() {
+		let path: Vec<Vec2> = walk(Vec2::new(0.5, 1.0), 0.5, 6, slope_course(2, 3, CollisionShape::SlopeRight));
+		assert_eq!(path, vec![
+			Vec2::new(1.0, 1.0),
+			Vec2::new(1.5, 1.5),
+			Vec2::new(2.0, 2.0),
+			Vec2::new(2.5, 2.0),
+			Vec2::new(3.0, 2.0),
+			Vec2::new(3.5, 2.0),
+		]);
+	}
+
+	#[test]
+	fn steps_up_slope_left() {
+		let path: Vec<Vec2> = walk(Vec2::new(-1.5, 1.0), -0.5, 6, slope_course(-3, -4, CollisionShape::SlopeLeft));
+		assert_eq!(path, vec![
+			Vec2::new(-2.0, 1.0),
+			Vec2::new(-2.5, 1.5),
+			Vec2::new(-3.0, 2.0),
+			Vec2::new(-3.5, 2.0),
+			Vec2::new(-4.0, 2.0),
+			Vec2::new(-4.5, 2.0),
+		]);
+	}
+
+	#[test]
+	fn tall_side_of_slope_is_a_wall() {
+		let (position, contacts) = sweep_aabb(Vec2::new(3.25, 1.0), MOB_SIZE, Vec2::new(-0.5, 0.0), None, slope_course(2, 5, CollisionShape::SlopeRight));
+		assert_eq!(position, Vec2::new(3.0, 1.0));
+		assert!(contacts.wall_left);
+	}
+
+	#[test]
+	fn half_slabs_block_their_half() {
+		let slab = |shape: CollisionShape| move |pos: TileAbsolutePosition| match (pos.0.x, pos.0.y) {
+			(0, 4) => shape,
+			_ => CollisionShape::None,
+		};
+		let (position, contacts) = sweep_aabb(Vec2::new(0.0, 6.0), MOB_SIZE, Vec2::new(0.0, -2.0), None, slab(CollisionShape::HalfBottom));
+		assert_eq!(position, Vec2::new(0.0, 4.5));
+		assert!(contacts.floor);
+		let (position, contacts) = sweep_aabb(Vec2::new(0.0, 0.0), MOB_SIZE, Vec2::new(0.0, 2.0), None, slab(CollisionShape::HalfTop));
+		assert_eq!(position, Vec2::new(0.0, 1.5));
+		assert!(contacts.ceiling && !contacts.floor);
+	}
+}
+
+#[derive(Component)]
+struct FallingTile {
+	id: TileId,
+}
+
+// Fixed ticks a tile waits before falling, so a loose column comes down one tile at a time
+const FALL_DELAY: u64 = 2;
+
+// Hook for tiles that fall, run when they're placed or something next to them changes
+fn schedule_fall(
+	In((pos, change)): In<(TileAbsolutePosition, TileChanged)>,
+	mut scheduler: ResMut<TickScheduler>,
+) {
+	if change.layer != TileLayer::Foreground { return; }
+	scheduler.schedule(pos, TileLayer::Foreground, FALL_DELAY);
+}
+
+// Scheduled by schedule_fall
+fn fall_when_unsupported(
+	In((pos, layer)): In<(TileAbsolutePosition, TileLayer)>,
+	mut commands: Commands,
+	mut to_change: ResMut<TileChangeQueue>,
+	tile_ids: Res<TileIds>,
+	chunks: Query<&Chunk>,
+	tiles: Query<&Tile>,
+) {
+	if layer != TileLayer::Foreground { return; }
+	let id: TileId = match chunks.tile_id_at(&tiles, pos, TileLayer::Foreground) {
+		Some(id) if tile_ids.by_id(id).falls => id,
+		_ => return,
+	};
+	if chunks.tile_id_at(&tiles, pos + (0, -1), TileLayer::Foreground) != Some(TileIds::AIR) { return; }
+	// Another tick this frame may have already knocked it loose
+	if to_change.queue.iter().any(|(_, layer, queued, _)| *queued == pos && *layer == TileLayer::Foreground) { return; }
+
+	to_change.push((TileIds::AIR, TileLayer::Foreground, pos, TileChangeCause::World));
+	let position: Vec2 = Vec2::new(pos.0.x as f32, pos.0.y as f32);
+	commands.spawn((
+		FallingTile { id: id },
+		Mob {
+			position: position,
+			previous_position: position,
+			size: Vec2::ONE,
+			pushes_mobs: false,
+			..default()
+		},
+		tile_ids.make_texture(id),
+		Transform::from_xyz(position.x * 8.0, position.y * 8.0, BLOCK_LAYER),
+	));
+}
+
+// There are no item drops yet, so a tile that can't settle where it lands goes in the first free cell above
+fn land_falling_tiles(
+	mut commands: Commands,
+	mut to_change: ResMut<TileChangeQueue>,
+	tile_ids: Res<TileIds>,
+	chunks: Query<&Chunk>,
+	tiles: Query<&Tile>,
+	falling: Query<(Entity, &Mob, &FallingTile)>,
+) {
+	for (entity, mob, falling_tile) in &falling {
+		if !mob.contacts.floor { continue; }
+		let landing: TileAbsolutePosition = TileAbsolutePosition::new(mob.position.x.round() as i64, mob.position.y.round() as i64);
+		// Stops at unloaded chunks, where the tile keeps waiting until there's room
+		let settled: Option<TileAbsolutePosition> = (0..)
+			.map(|up| (landing + (0, up), chunks.tile_id_at(&tiles, landing + (0, up), TileLayer::Foreground)))
+			.take_while(|(_, id)| id.is_some())
+			.find(|(at, id)| {
+				(*id == Some(TileIds::AIR) || tile_ids.by_id(id.unwrap()).fluid.is_some()) &&
+					!to_change.queue.iter().any(|(_, layer, queued, _)| queued == at && *layer == TileLayer::Foreground)
+			})
+			.map(|(at, _)| at);
+		if let Some(at) = settled {
+			to_change.push((falling_tile.id, TileLayer::Foreground, at, TileChangeCause::World));
+			commands.entity(entity).despawn();
+		}
+	}
+}
+
+#[cfg(test)]
+mod falling_tile_tests {
+	use super::*;
+	use bevy::ecs::system::RunSystemOnce;
+
+	// One chunk at the origin, air apart from the given foreground tiles
+	fn world_with(foreground: &[((i64, i64), TileId)]) -> World {
+		let mut world: World = World::new();
+		world.insert_resource(TileIds::new(core::array::from_fn(|id| {
+			let tile: TileData = TileData::new(false, SmoothGroups::NONE, id == TileIds::STONE, (Handle::default(), Handle::default()));
+			match id {
+				TileIds::WATER => tile.with_fluid(FluidData { flow_interval: 1, drag: 0.0 }),
+				_ => tile,
+			}
+		})));
+		world.init_resource::<TileChangeQueue>();
+		world.init_resource::<FluidLevels>();
+		world.init_resource::<Messages<TileChanged>>();
+		let tiles: [[(Entity, Entity); Chunk::WIDTH]; Chunk::HEIGHT] = core::array::from_fn(|y| core::array::from_fn(|x| {
+			let id: TileId = foreground.iter()
+				.find(|(at, _)| *at == (x as i64, y as i64))
+				.map_or(TileIds::AIR, |(_, id)| *id);
+			(world.spawn(Tile { id: TileIds::AIR }).id(), world.spawn(Tile { id: id }).id())
+		}));
+		world.spawn(Chunk::new(tiles, 0, 0));
+		world
+	}
+
+	fn drop_sand(world: &mut World, at: (i64, i64)) {
+		let mut mob: Mob = Mob {
+			position: Vec2::new(at.0 as f32, at.1 as f32),
+			size: Vec2::ONE,
+			..default()
+		};
+		mob.contacts.floor = true;
+		world.spawn((FallingTile { id: TileIds::SAND }, mob));
+		world.run_system_once(land_falling_tiles).unwrap();
+	}
+
+	#[test]
+	fn settles_above_an_occupied_landing_spot() {
+		let mut world: World = world_with(&[((5, 0), TileIds::STONE), ((5, 1), TileIds::STONE)]);
+		drop_sand(&mut world, (5, 1));
+		let queue = &world.resource::<TileChangeQueue>().queue;
+		assert_eq!(queue.len(), 1);
+		assert!(queue[0].0 == TileIds::SAND && queue[0].2 == TileAbsolutePosition::new(5, 2));
+		assert!(world.query::<&FallingTile>().iter(&world).next().is_none());
+	}
+
+	#[test]
+	fn two_tiles_landing_together_stack() {
+		let mut world: World = world_with(&[((5, 0), TileIds::STONE)]);
+		drop_sand(&mut world, (5, 1));
+		drop_sand(&mut world, (5, 1));
+		let landed: Vec<TileAbsolutePosition> = world.resource::<TileChangeQueue>().queue.iter().map(|(_, _, at, _)| *at).collect();
+		assert!(landed == vec![TileAbsolutePosition::new(5, 1), TileAbsolutePosition::new(5, 2)]);
+	}
+
+	#[test]
+	fn sand_falling_into_water_clears_its_level() {
+		let water: TileAbsolutePosition = TileAbsolutePosition::new(5, 1);
+		let mut world: World = world_with(&[((5, 0), TileIds::STONE), ((5, 1), TileIds::WATER)]);
+		world.resource_mut::<FluidLevels>().set(water, TileIds::WATER, FluidLevels::MAX);
+		drop_sand(&mut world, (5, 1));
+		let (id, layer, at, cause) = world.resource::<TileChangeQueue>().queue[0];
+		assert!(id == TileIds::SAND && at == water);
+
+		world.write_message(TileChanged { pos: at, layer: layer, old: TileIds::WATER, new: id, cause: cause });
+		world.run_system_once(wake_fluids).unwrap();
+		assert_eq!(world.resource::<FluidLevels>().level(water), 0);
+	}
+}
+
+fn update_mob_sprites(
+	fixed_time: Res<Time<Fixed>>,
+	mut mobs: Query<(&Mob, &mut Transform, Option<&SpriteOffset>), Without<Player>>,
+) {
+	for (mob, mut transfem, offset) in &mut mobs {
+		let position: Vec2 = mob.interpolated_position(fixed_time.overstep_fraction()) * 8.0 + match offset {
+			Some(SpriteOffset(offset)) => *offset,
+			None => Vec2::ZERO,
+		};
+		transfem.translation.x = position.x;
+		transfem.translation.y = position.y;
+	}
+}
+
+// Spent on searches each frame, anything unfinished carries over to the next
+const PATHFINDING_BUDGET: std::time::Duration = std::time::Duration::from_millis(2);
+// Searches give up after visiting this many positions
+const PATH_NODE_LIMIT: usize = 4096;
+const PATH_STEPS_PER_CHECK: usize = 64;
+// Finished paths kept around, the least recently used goes first
+const PATH_CACHE_LIMIT: usize = 256;
+
+// What a mob's body can get over, in tiles
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+struct Reach {
+	jump: i64,
+	fall: i64,
+	height: i64,
+}
+
+impl Reach {
+	// Falls are capped at a few jump heights so paths don't drop somewhere the mob can't climb back out of
+	#[inline]
+	fn new(config: &PhysicsConfig, size: Vec2) -> Self {
+		let jump: i64 = ((config.jump_impulse * config.jump_impulse) / (2.0 * config.gravity.max(0.001))).floor().max(1.0) as i64;
+		Self {
+			jump: jump,
+			fall: jump * 3,
+			height: size.y.ceil() as i64,
+		}
+	}
+}
+
+#[derive(Component, Default)]
+struct PathFollower {
+	goal: Option<TileAbsolutePosition>,
+	path: VecDeque<TileAbsolutePosition>,
+	pending: bool,
+}
+
+struct PathSearch {
+	from: TileAbsolutePosition,
+	to: TileAbsolutePosition,
+	reach: Reach,
+	open: std::collections::BinaryHeap<std::cmp::Reverse<(i64, i64, i64)>>,
+	cost: HashMap<TileAbsolutePosition, i64>,
+	came_from: HashMap<TileAbsolutePosition, TileAbsolutePosition>,
+	chunks: HashSet<ChunkPosition>,
+}
+
+impl PathSearch {
+	fn new(from: TileAbsolutePosition, to: TileAbsolutePosition, reach: Reach) -> Self {
+		let mut search: Self = Self {
+			from: from,
+			to: to,
+			reach: reach,
+			open: std::collections::BinaryHeap::new(),
+			cost: HashMap::new(),
+			came_from: HashMap::new(),
+			chunks: HashSet::new(),
+		};
+		search.cost.insert(from, 0);
+		search.open.push(std::cmp::Reverse((Self::estimate(from, to), from.0.x, from.0.y)));
+		search
+	}
+
+	// Every move covers at most one column and costs at least 10, so this never overestimates
+	#[inline]
+	fn estimate(from: TileAbsolutePosition, to: TileAbsolutePosition) -> i64 { (to.0.x - from.0.x).abs() * 10 }
+
+	// Positions a body can stand at or hang from next to pos, with what getting there costs
+	fn moves(
+		pos: TileAbsolutePosition,
+		reach: Reach,
+		shape_at: &impl Fn(TileAbsolutePosition) -> Option<(CollisionShape, bool)>,
+	) -> Vec<(TileAbsolutePosition, i64)> {
+		let passable = |at: TileAbsolutePosition| matches!(shape_at(at), Some((CollisionShape::None | CollisionShape::Platform, _)));
+		let climbable = |at: TileAbsolutePosition| matches!(shape_at(at), Some((_, true)));
+		let clear = |at: TileAbsolutePosition, height: i64| (0..height).all(|above| passable(at + (0, above)));
+		let standable = |at: TileAbsolutePosition| clear(at, reach.height) && (
+			climbable(at) ||
+			matches!(shape_at(at + (0, -1)), Some((shape, _)) if shape != CollisionShape::None)
+		);
+
+		let mut moves: Vec<(TileAbsolutePosition, i64)> = Vec::new();
+		for side in [-1, 1] {
+			let beside: TileAbsolutePosition = pos + (side, 0);
+			if standable(beside) {
+				moves.push((beside, 10));
+			} else if clear(beside, reach.height) {
+				for drop in 1..=reach.fall {
+					let landing: TileAbsolutePosition = beside + (0, -drop);
+					if !passable(landing) { break; }
+					if standable(landing) {
+						moves.push((landing, 10 + drop * 2));
+						break;
+					}
+				}
+			}
+			for rise in 1..=reach.jump {
+				if !passable(pos + (0, reach.height + rise - 1)) { break; }
+				let landing: TileAbsolutePosition = beside + (0, rise);
+				if standable(landing) {
+					moves.push((landing, 10 + rise * 5));
+					break;
+				}
+			}
+		}
+		if (0..reach.height).any(|above| climbable(pos + (0, above))) {
+			for offset in [(0, 1), (0, -1)] {
+				if standable(pos + offset) {
+					moves.push((pos + offset, 10));
+				}
+			}
+		}
+		moves
+	}
+
+	// Some(path) once the search is over, an empty path meaning there isn't one
+	fn step(
+		&mut self,
+		steps: usize,
+		shape_at: &impl Fn(TileAbsolutePosition) -> Option<(CollisionShape, bool)>,
+	) -> Option<Vec<TileAbsolutePosition>> {
+		for _ in 0..steps {
+			let pos: TileAbsolutePosition = match self.open.pop() {
+				Some(std::cmp::Reverse((_, x, y))) => TileAbsolutePosition::new(x, y),
+				None => return Some(Vec::new()),
+			};
+			if pos == self.to {
+				let mut path: Vec<TileAbsolutePosition> = vec![pos];
+				let mut at: TileAbsolutePosition = pos;
+				while let Some(previous) = self.came_from.get(&at) {
+					at = *previous;
+					if at != self.from {
+						path.push(at);
+					}
+				}
+				path.reverse();
+				return Some(path);
+			}
+			if self.cost.len() > PATH_NODE_LIMIT {
+				return Some(Vec::new());
+			}
+
+			// Every cell looked at decides the result, including the ones that ruled a move out
+			let inspected: std::cell::RefCell<HashSet<ChunkPosition>> = std::cell::RefCell::new(HashSet::new());
+			let moves: Vec<(TileAbsolutePosition, i64)> = Self::moves(pos, self.reach, &|at: TileAbsolutePosition| {
+				inspected.borrow_mut().insert(at.to_positions().0);
+				shape_at(at)
+			});
+			self.chunks.extend(inspected.into_inner());
+
+			let cost: i64 = self.cost[&pos];
+			for (next, step_cost) in moves {
+				let next_cost: i64 = cost + step_cost;
+				if self.cost.get(&next).is_none_or(|known| next_cost < *known) {
+					self.cost.insert(next, next_cost);
+					self.came_from.insert(next, pos);
+					self.open.push(std::cmp::Reverse((next_cost + Self::estimate(next, self.to), next.0.x, next.0.y)));
+				}
+			}
+		}
+		None
+	}
+}
+
+#[cfg(test)]
+mod path_search_tests {
+	use super::*;
+
+	// Rows from the top down, # is solid, H a ladder and anything else air. Column 0 is x = 0, the bottom row y = 0.
+	fn grid(rows: &'static [&'static str]) -> impl Fn(TileAbsolutePosition) -> Option<(CollisionShape, bool)> {
+		move |pos| {
+			let row: &str = rows.get(usize::try_from(rows.len() as i64 - 1 - pos.0.y).ok()?)?;
+			match row.as_bytes().get(usize::try_from(pos.0.x).ok()?)? {
+				b'#' => Some((CollisionShape::Full, false)),
+				b'H' => Some((CollisionShape::None, true)),
+				_ => Some((CollisionShape::None, false)),
+			}
+		}
+	}
+
+	fn find(
+		shape_at: &impl Fn(TileAbsolutePosition) -> Option<(CollisionShape, bool)>,
+		from: (i64, i64),
+		to: (i64, i64),
+		reach: Reach,
+	) -> (Vec<TileAbsolutePosition>, HashSet<ChunkPosition>) {
+		let mut search: PathSearch = PathSearch::new(TileAbsolutePosition::new(from.0, from.1), TileAbsolutePosition::new(to.0, to.1), reach);
+		loop {
+			if let Some(path) = search.step(PATH_STEPS_PER_CHECK, shape_at) {
+				return (path, search.chunks);
+			}
 		}
 	}
+
+	#[inline]
+	fn reach(jump: i64, fall: i64, height: i64) -> Reach { Reach { jump: jump, fall: fall, height: height } }
+
+	#[test]
+	fn jumps_only_as_high_as_reach() {
+		let wall = grid(&[
+			"..........",
+			"..........",
+			"......#...",
+			"......#...",
+			"##########",
+		]);
+		let (path, _) = find(&wall, (1, 1), (8, 1), reach(2, 3, 2));
+		assert!(path.contains(&TileAbsolutePosition::new(6, 3)));
+		assert!(path.last() == Some(&TileAbsolutePosition::new(8, 1)));
+		assert!(find(&wall, (1, 1), (8, 1), reach(1, 3, 2)).0.is_empty());
+	}
+
+	#[test]
+	fn falls_only_as_far_as_reach() {
+		let cliff = grid(&[
+			"..........",
+			"..........",
+			"####......",
+			"####......",
+			"####......",
+			"####......",
+			"##########",
+		]);
+		assert!(find(&cliff, (1, 5), (8, 1), reach(1, 3, 2)).0.is_empty());
+		let (path, _) = find(&cliff, (1, 5), (8, 1), reach(1, 4, 2));
+		assert!(path.contains(&TileAbsolutePosition::new(4, 1)));
+	}
+
+	#[test]
+	fn needs_head_clearance() {
+		let tunnel = grid(&[
+			"##########",
+			"..........",
+			"..........",
+			"##########",
+		]);
+		assert_eq!(find(&tunnel, (0, 1), (9, 1), reach(1, 3, 2)).0.len(), 9);
+		assert!(find(&tunnel, (0, 1), (9, 1), reach(1, 3, 3)).0.is_empty());
+
+		// Nothing to stand under, but the ceiling is in the way of the jump
+		let low_ceiling = grid(&[
+			"..........",
+			"####......",
+			"..........",
+			"....#.....",
+			"##########",
+		]);
+		assert!(find(&low_ceiling, (1, 1), (6, 1), reach(2, 3, 2)).0.is_empty());
+		let open = grid(&[
+			"..........",
+			"..........",
+			"..........",
+			"....#.....",
+			"##########",
+		]);
+		assert!(!find(&open, (1, 1), (6, 1), reach(2, 3, 2)).0.is_empty());
+	}
+
+	#[test]
+	fn climbs_ladders() {
+		let shaft = grid(&[
+			"....",
+			"....",
+			".H##",
+			".H##",
+			".H##",
+			"####",
+		]);
+		let (path, _) = find(&shaft, (0, 1), (3, 4), reach(1, 3, 2));
+		assert!(path.contains(&TileAbsolutePosition::new(1, 3)));
+		assert!(path.last() == Some(&TileAbsolutePosition::new(3, 4)));
+	}
+
+	#[test]
+	fn changes_next_to_the_path_forget_it() {
+		// A wall just over the chunk border on the left is never walked on, but it's why the path doesn't go that way
+		let walled = |pos: TileAbsolutePosition| match pos.0.x {
+			-1 => Some((CollisionShape::Full, false)),
+			_ => grid(&["....", "....", "....", "####"])(pos),
+		};
+		let (path, chunks) = find(&walled, (0, 1), (3, 1), reach(1, 3, 2));
+		assert_eq!(path.len(), 3);
+		assert!(chunks.contains(&ChunkPosition::new(-1, 0)));
+
+		let key: PathKey = (TileAbsolutePosition::new(0, 1), TileAbsolutePosition::new(3, 1), reach(1, 3, 2));
+		let mut pathfinder: Pathfinder = Pathfinder::default();
+		pathfinder.remember(key, CachedPath { path: path, chunks: chunks });
+		pathfinder.forget_chunks(&HashSet::from([ChunkPosition::new(5, 5)]));
+		assert!(pathfinder.cached(key).is_some());
+		pathfinder.forget_chunks(&HashSet::from([ChunkPosition::new(-1, 0)]));
+		assert!(pathfinder.cached(key).is_none());
+	}
 }
 
-fn update_camera (
-	mut cam: Query<&mut Transform, (With<Camera>, Without<Player>)>,
-	mut player: Query<(&Mob, &mut Transform), With<Player>>,
-) {
-	if let Ok(mut transfem) = cam.single_mut() {
-		if let Ok((mob, mut player_transfem)) = player.single_mut() {
-			player_transfem.translation.x = mob.position.x * 8.0;
-			player_transfem.translation.y = mob.position.y * 8.0 + 12.0;
-			transfem.translation.x = mob.position.x * 8.0;
-			transfem.translation.y = mob.position.y * 8.0;
+struct CachedPath {
+	path: Vec<TileAbsolutePosition>,
+	chunks: HashSet<ChunkPosition>,
+}
+
+type PathKey = (TileAbsolutePosition, TileAbsolutePosition, Reach);
+
+#[derive(Resource, Default)]
+struct Pathfinder {
+	searches: VecDeque<(Entity, PathSearch)>,
+	cache: HashMap<PathKey, CachedPath>,
+	// Cached keys, least recently used first
+	recent: VecDeque<PathKey>,
+}
+
+impl Pathfinder {
+	fn cached(&mut self, key: PathKey) -> Option<&CachedPath> {
+		let index: usize = self.recent.iter().position(|recent| *recent == key)?;
+		self.recent.remove(index);
+		self.recent.push_back(key);
+		self.cache.get(&key)
+	}
+
+	fn remember(&mut self, key: PathKey, cached: CachedPath) {
+		if self.cache.insert(key, cached).is_some() {
+			self.recent.retain(|recent| *recent != key);
+		}
+		self.recent.push_back(key);
+		while self.recent.len() > PATH_CACHE_LIMIT {
+			if let Some(oldest) = self.recent.pop_front() {
+				self.cache.remove(&oldest);
+			}
 		}
 	}
+
+	fn forget_chunks(&mut self, touched: &HashSet<ChunkPosition>) {
+		self.cache.retain(|_, cached| cached.chunks.is_disjoint(touched));
+		let cache: &HashMap<PathKey, CachedPath> = &self.cache;
+		self.recent.retain(|key| cache.contains_key(key));
+	}
 }
 
-fn player_input (
-	windows: Query<&Window, With<bevy::window::PrimaryWindow>>,
-	keys: Res<ButtonInput<KeyCode>>,
-	mouse_keys: Res<ButtonInput<MouseButton>>,
-	mut update_tiles: ResMut<TilesShouldUpdate>,
-	mut update_queue: ResMut<TileChangeQueue>,
-	pixel_projection: Query<&Projection>,
+fn find_paths(
+	mut pathfinder: ResMut<Pathfinder>,
+	mut changed: MessageReader<TileChanged>,
+	config: Res<PhysicsConfig>,
+	tile_ids: Res<TileIds>,
 	chunks: Query<&Chunk>,
 	tiles: Query<&Tile>,
-	mut query: Query<(&mut Player, &mut Mob)>,
+	mut followers: Query<(Entity, &Mob, &mut PathFollower, Option<&MobPhysics>)>,
 ) {
-	if let Ok((mut player, mut mob)) = query.single_mut() {
-		if keys.pressed(KeyCode::Space) {
-			mob.jump_state = JumpState::TryJump;
-		} else {
-			mob.jump_state = JumpState::None;
+	let started: std::time::Instant = std::time::Instant::now();
+
+	// Anything that looked at a changed chunk might be wrong now
+	let touched: HashSet<ChunkPosition> = changed.read()
+		.filter(|change| change.layer == TileLayer::Foreground)
+		.map(|change| change.pos.to_positions().0)
+		.collect();
+	if !touched.is_empty() {
+		pathfinder.forget_chunks(&touched);
+		for (_, search) in pathfinder.searches.iter_mut() {
+			if !search.chunks.is_disjoint(&touched) {
+				*search = PathSearch::new(search.from, search.to, search.reach);
+			}
 		}
-		mob.walk_state = WalkState::None;
-		if keys.pressed(KeyCode::KeyA) {
-			mob.walk_state = WalkState::TryLeft;
+	}
+
+	for (entity, mob, mut follower, overridden) in &mut followers {
+		let goal: TileAbsolutePosition = match follower.goal {
+			Some(goal) if follower.path.is_empty() && !follower.pending => goal,
+			_ => continue,
+		};
+		let reach: Reach = Reach::new(match overridden {
+			Some(MobPhysics(mob_config)) => mob_config,
+			None => &config,
+		}, mob.size);
+		let from: TileAbsolutePosition = mob.standing_at();
+		if from == goal { continue; }
+		match pathfinder.cached((from, goal, reach)) {
+			Some(cached) => follower.path = cached.path.iter().copied().collect(),
+			None => {
+				pathfinder.searches.push_back((entity, PathSearch::new(from, goal, reach)));
+				follower.pending = true;
+			},
 		}
-		if keys.pressed(KeyCode::KeyD) {
-			mob.walk_state = WalkState::TryRight;
+	}
+
+	let shape_at = |pos: TileAbsolutePosition| -> Option<(CollisionShape, bool)> {
+		chunks.tile_id_at(&tiles, pos, TileLayer::Foreground).map(|id| (tile_ids.by_id(id).shape, tile_ids.by_id(id).climbable))
+	};
+	while started.elapsed() < PATHFINDING_BUDGET {
+		let (entity, mut search) = match pathfinder.searches.pop_front() {
+			Some(search) => search,
+			None => break,
+		};
+		match search.step(PATH_STEPS_PER_CHECK, &shape_at) {
+			Some(path) => {
+				if let Ok((_, _, mut follower, _)) = followers.get_mut(entity) {
+					follower.pending = false;
+					if follower.goal == Some(search.to) {
+						follower.path = path.iter().copied().collect();
+					}
+				}
+				pathfinder.remember((search.from, search.to, search.reach), CachedPath {
+					path: path,
+					chunks: search.chunks,
+				});
+			},
+			None => pathfinder.searches.push_front((entity, search)),
 		}
-		if keys.just_pressed(KeyCode::KeyZ) {
-			if player.selected_block > TileIds::AIR + 1 {
-				player.selected_block -= 1;
-			}
-		} else if keys.just_pressed(KeyCode::KeyX) {
-			if player.selected_block < TileIds::BLOCKS - 1 {
-				player.selected_block += 1;
-			}
+	}
+}
+
+#[cfg(test)]
+mod path_cache_tests {
+	use super::*;
+
+	fn key(x: i64) -> PathKey {
+		(TileAbsolutePosition::new(0, 0), TileAbsolutePosition::new(x, 0), Reach { jump: 1, fall: 3, height: 3 })
+	}
+
+	fn path_through(chunk: ChunkPosition) -> CachedPath {
+		CachedPath {
+			path: Vec::new(),
+			chunks: HashSet::from([chunk]),
 		}
+	}
 
-		let window = windows.single().unwrap();
+	#[test]
+	fn cache_drops_least_recently_used() {
+		let mut pathfinder: Pathfinder = Pathfinder::default();
+		for x in 0..PATH_CACHE_LIMIT as i64 {
+			pathfinder.remember(key(x), path_through(ChunkPosition::new(0, 0)));
+		}
+		assert!(pathfinder.cached(key(0)).is_some());
+		pathfinder.remember(key(-1), path_through(ChunkPosition::new(0, 0)));
 
-		if mouse_keys.any_just_pressed([MouseButton::Left, MouseButton::Right]) {
-		if let Some(cursor) = window.cursor_position() {
-		if let Ok(ortho_projection) = pixel_projection.single() {
-			let projection: OrthographicProjection = match ortho_projection {
-				Projection::Orthographic(ortho) => ortho.clone(),
-				_ => OrthographicProjection::default_2d()
-			};
-//			let play_width: f32 =
-//				projection.area.width() as f32 * 0.5;
-//			let play_height: f32 =
-//				projection.area.height() as f32 * 0.5;
-			let real_mouse_x: f32 =
-				((cursor.x - (window.width() * 0.5)) * projection.scale as f32).round();
-			let real_mouse_y: f32 =
-				-((cursor.y - (window.height() * 0.5)) * projection.scale as f32).round();
-
-//			if real_mouse_x <= play_width && real_mouse_y <= play_height {
-				let tile_mouse_position: TileAbsolutePosition = TileAbsolutePosition::new(
-					(mob.position.x + (real_mouse_x / 8.0)).round() as i64,
-					(mob.position.y + (real_mouse_y / 8.0)).round() as i64
-				);
+		assert_eq!(pathfinder.cache.len(), PATH_CACHE_LIMIT);
+		assert_eq!(pathfinder.recent.len(), PATH_CACHE_LIMIT);
+		assert!(pathfinder.cached(key(0)).is_some());
+		assert!(pathfinder.cached(key(1)).is_none());
+		assert!(pathfinder.cached(key(-1)).is_some());
+	}
 
-				if mouse_keys.just_pressed(MouseButton::Left) {
-				if let Some(clicking_entity) = chunks.tile_at(tile_mouse_position) {
-				if let Ok(clicked_tile) = tiles.get(clicking_entity.1) {
-				if clicked_tile.id == TileIds::AIR {
-					update_queue.push((
-						player.selected_block,
-						true,
-						tile_mouse_position
-					));
-					update_tiles.should_update = true;
-				}}}}
-				if mouse_keys.just_pressed(MouseButton::Right) {
-				if let Some(clicking_entity) = chunks.tile_at(tile_mouse_position) {
-				if let Ok(clicked_tile) = tiles.get(clicking_entity.1) {
-				if clicked_tile.id != TileIds::AIR {
-					update_queue.push((
-						TileIds::AIR,
-						true,
-						tile_mouse_position
-					));
-					update_tiles.should_update = true;
-				}}}}
-//			}
-		}}}
+	#[test]
+	fn changed_chunks_are_forgotten() {
+		let mut pathfinder: Pathfinder = Pathfinder::default();
+		pathfinder.remember(key(1), path_through(ChunkPosition::new(0, 0)));
+		pathfinder.remember(key(2), path_through(ChunkPosition::new(1, 0)));
+		pathfinder.forget_chunks(&HashSet::from([ChunkPosition::new(1, 0)]));
+		assert!(pathfinder.cached(key(1)).is_some());
+		assert!(pathfinder.cached(key(2)).is_none());
+		assert_eq!(pathfinder.recent.len(), 1);
 	}
 }
 
-const GRAVITY: f32 = 9.81;
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Behaviour {
+	Wander,
+	Follow,
+	Flee,
+}
 
-fn do_physics(
+// Follow and Flee only kick in once the player is within sight, otherwise the creature wanders
+#[derive(Component)]
+struct Creature {
+	behaviour: Behaviour,
+	sight: f32,
+	wandering: WalkState,
+	wander_timer: Timer,
+}
+
+#[derive(Component)]
+struct SpriteOffset(Vec2);
+
+const MAX_CREATURES: usize = 8;
+const CREATURE_SPAWN_DISTANCE: (f32, f32) = (24.0, 48.0);
+const CREATURE_DESPAWN_DISTANCE: f32 = 96.0;
+// Creatures amble, so they walk slower than the player
+const CREATURE_WALK_SCALE: f32 = 0.6;
+
+#[derive(Resource)]
+struct CreatureSpawner {
+	rng: XorShift,
+	timer: Timer,
+	// Sprite sheets, with the behaviour creatures using them get
+	kinds: [(PlayerAnimation, Behaviour); 2],
+}
+
+fn spawn_creatures(
+	mut commands: Commands,
 	time: Res<Time>,
+	mut spawner: ResMut<CreatureSpawner>,
+	config: Res<PhysicsConfig>,
 	tile_ids: Res<TileIds>,
-	mut mob_query: Query<&mut Mob>,
 	chunks: Query<&Chunk>,
-	blocks: Query<&Tile>,
+	tiles: Query<&Tile>,
+	player: Query<&Mob, With<Player>>,
+	creatures: Query<(Entity, &Mob), With<Creature>>,
 ) {
-	if let Ok(mut mob) = mob_query.single_mut() {
-		let mut new_velocity = mob.velocity;
-		new_velocity += if mob.touching_grass {(
-			match mob.jump_state {
-				JumpState::TryJump => Vec2::new(0.0, 30.0),
-				_ => Vec2::ZERO,
-			} +
-			match mob.walk_state {
-				WalkState::None => Vec2::ZERO,
-				_ => Vec2::new(mob.walk_state as i32 as f32 * 5.0, 0.0),
-			}
-		)} else {
-			Vec2::ZERO
-		} + Vec2::new(0.0, -GRAVITY * time.delta_secs() * 8.0);
-		let mut new_loc: Vec2 = mob.position + new_velocity * time.delta_secs();
-		mob.touching_grass = false;
-
-		for y in 0..=mob.size.y.trunc() as i64 {
-		for x in 0..=mob.size.x.trunc() as i64 {
-			let checking_x: i64 = x + match new_loc.x.is_sign_negative() {
-				true => (new_loc.x - 1.0).trunc() as i64,
-				false => new_loc.x.trunc() as i64
+	let center: Vec2 = match player.single() {
+		Ok(mob) => mob.position,
+		Err(_) => return,
+	};
+	for (entity, mob) in &creatures {
+		if mob.position.distance(center) > CREATURE_DESPAWN_DISTANCE {
+			commands.entity(entity).despawn();
+		}
+	}
+
+	spawner.timer.tick(time.delta());
+	if !spawner.timer.just_finished() || creatures.iter().count() >= MAX_CREATURES { return; }
+
+	let side: f32 = match spawner.rng.next_u64() % 2 {
+		0 => -1.0,
+		_ => 1.0,
+	};
+	let x: i64 = (center.x + side * spawner.rng.range_f32(CREATURE_SPAWN_DISTANCE.0, CREATURE_SPAWN_DISTANCE.1)).floor() as i64;
+	let is_solid = |pos: TileAbsolutePosition| -> Option<bool> {
+		chunks.tile_id_at(&tiles, pos, TileLayer::Foreground).map(|id| tile_ids.by_id(id).solid)
+	};
+	// First spot going down with ground underneath and room for a three tile tall body
+	let ground: Option<i64> = (center.y.floor() as i64 - 16..center.y.floor() as i64 + 16).rev().find(
+		|y| is_solid(TileAbsolutePosition::new(x, y - 1)) == Some(true) &&
+			(0..3).all(|above| is_solid(TileAbsolutePosition::new(x, y + above)) == Some(false))
+	);
+	let y: i64 = match ground {
+		Some(y) => y,
+		None => return,
+	};
+
+	let kind: usize = (spawner.rng.next_u64() % spawner.kinds.len() as u64) as usize;
+	let (animation, behaviour) = spawner.kinds[kind].clone();
+	let position: Vec2 = Vec2::new(x as f32, y as f32);
+	commands.spawn((
+		Creature {
+			behaviour: behaviour,
+			sight: 16.0,
+			wandering: WalkState::None,
+			wander_timer: Timer::from_seconds(2.0, TimerMode::Repeating),
+		},
+		Mob {
+			position: position,
+			previous_position: position,
+			..default()
+		},
+		Sprite {
+			image: animation.idle_sheet.0.clone(),
+			texture_atlas: Some(TextureAtlas {
+				layout: animation.idle_sheet.1.clone(),
+				index: 0,
+			}),
+			..default()
+		},
+		Transform::from_xyz(position.x * 8.0, position.y * 8.0, MOB_LAYER),
+		SpriteOffset(Vec2::new(0.0, 12.0)),
+		PlayerAnimationTimer(Timer::from_seconds(0.1, TimerMode::Repeating)),
+		animation,
+		// Taken from the global config when spawned, so physics panel changes reach creatures spawned afterwards
+		MobPhysics(PhysicsConfig {
+			walk_acceleration: config.walk_acceleration * CREATURE_WALK_SCALE,
+			..*config
+		}),
+		PathFollower::default(),
+	));
+}
+
+fn creature_ai(
+	time: Res<Time>,
+	mut spawner: ResMut<CreatureSpawner>,
+	tile_ids: Res<TileIds>,
+	chunks: Query<&Chunk>,
+	tiles: Query<&Tile>,
+	player: Query<&Mob, With<Player>>,
+	mut creatures: Query<(&mut Creature, &mut Mob, Option<&mut PathFollower>), Without<Player>>,
+) {
+	let target: Option<Vec2> = player.single().ok().map(|mob| mob.position);
+	let target_standing: Option<TileAbsolutePosition> = player.single().ok().map(|mob| mob.standing_at());
+	let is_solid = |pos: TileAbsolutePosition| -> bool {
+		match chunks.tile_id_at(&tiles, pos, TileLayer::Foreground) {
+			Some(id) => tile_ids.by_id(id).solid,
+			None => true,
+		}
+	};
+
+	for (mut creature, mut mob, follower) in &mut creatures {
+		creature.wander_timer.tick(time.delta());
+		if creature.wander_timer.just_finished() {
+			creature.wandering = match spawner.rng.next_u64() % 3 {
+				0 => WalkState::TryLeft,
+				1 => WalkState::None,
+				_ => WalkState::TryRight,
 			};
-			let checking_y: i64 = y + match new_loc.y.is_sign_negative() {
-				true => (new_loc.y - 1.0).trunc() as i64,
-				false => new_loc.y.trunc() as i64
+		}
+
+		let toward: Option<f32> = target
+			.filter(|target| target.distance(mob.position) <= creature.sight)
+			.map(|target| target.x - mob.position.x);
+		mob.walk_state = match (creature.behaviour, toward) {
+			(Behaviour::Follow, Some(toward)) if toward.abs() > 1.0 => match toward > 0.0 {
+				true => WalkState::TryRight,
+				false => WalkState::TryLeft,
+			},
+			(Behaviour::Follow, Some(_)) => WalkState::None,
+			(Behaviour::Flee, Some(toward)) => match toward > 0.0 {
+				true => WalkState::TryLeft,
+				false => WalkState::TryRight,
+			},
+			_ => creature.wandering,
+		};
+		mob.climb_direction = ClimbDirection::None;
+
+		// Following takes the pathfinder's route when it has one instead of walking straight at the player
+		let mut waypoint: Option<TileAbsolutePosition> = None;
+		if let Some(mut follower) = follower {
+			let goal: Option<TileAbsolutePosition> = match (creature.behaviour, toward) {
+				(Behaviour::Follow, Some(_)) => target_standing,
+				_ => None,
 			};
-			let mob_x: i64 = x + match mob.position.x.is_sign_negative() {
-				true => (mob.position.x - 1.0).trunc() as i64,
-				false => mob.position.x.trunc() as i64
+			if follower.goal != goal {
+				follower.goal = goal;
+				follower.path.clear();
+			}
+			let standing: TileAbsolutePosition = mob.standing_at();
+			while follower.path.front() == Some(&standing) {
+				follower.path.pop_front();
+			}
+			waypoint = follower.path.front().copied();
+		}
+		if let Some(waypoint) = waypoint {
+			let feet: TileAbsolutePosition = mob.standing_at();
+			mob.walk_state = match waypoint.0.x - feet.0.x {
+				0 => WalkState::None,
+				x if x > 0 => WalkState::TryRight,
+				_ => WalkState::TryLeft,
 			};
-			let mob_y: i64 = y + match mob.position.y.is_sign_negative() {
-				true => (mob.position.y - 1.0).trunc() as i64,
-				false => mob.position.y.trunc() as i64
+			mob.climb_direction = match waypoint.0.y - feet.0.y {
+				0 => ClimbDirection::None,
+				y if y > 0 => ClimbDirection::TryUp,
+				_ => ClimbDirection::TryDown,
 			};
+		}
 
-			if let Some(tile_colliding) = chunks.tile_at(TileAbsolutePosition::new(mob_x, checking_y)) {
-			if let Ok(tile) = blocks.get(tile_colliding.1) {
-			if tile_ids.by_tile(tile).solid {
-				new_velocity.y = 0.0;
-				new_loc.y = mob.position.y.trunc();
-				new_velocity.x -= new_velocity.x * 10.0 * time.delta_secs();
-				mob.touching_grass = true;
-			}}}
-			if let Some(tile_colliding) = chunks.tile_at(TileAbsolutePosition::new(checking_x, mob_y)) {
-			if let Ok(tile) = blocks.get(tile_colliding.1) {
-			if tile_ids.by_tile(tile).solid {
-				new_velocity.x = 0.0;
-				new_loc.x = mob.position.x;
-			}}}
-			else if let Some(tile_colliding) = chunks.tile_at(TileAbsolutePosition::new(checking_x, checking_y)) {
-			if let Ok(tile) = blocks.get(tile_colliding.1) {
-			if tile_ids.by_tile(tile).solid {
-				new_velocity.x = 0.0;
-				new_loc.x = mob.position.x;
-			}}}
-		}}
+		// Hop a single block in the way, but not anything taller
+		let blocked: bool = match mob.walk_state {
+			WalkState::TryLeft => mob.contacts.wall_left,
+			WalkState::TryRight => mob.contacts.wall_right,
+			WalkState::None => false,
+		};
+		mob.jump_state = JumpState::None;
+		if waypoint.is_some_and(|waypoint| waypoint.0.y > mob.standing_at().0.y) && mob.touching_grass {
+			mob.jump_state = JumpState::TryJump;
+		} else if blocked && mob.touching_grass {
+			let ahead: i64 = match mob.walk_state {
+				WalkState::TryRight => (mob.position.x + mob.size.x).round() as i64,
+				_ => mob.position.x.round() as i64 - 1,
+			};
+			let feet: i64 = mob.position.y.round() as i64;
+			if (1..=mob.size.y.ceil() as i64).all(|above| !is_solid(TileAbsolutePosition::new(ahead, feet + above))) {
+				mob.jump_state = JumpState::TryJump;
+			} else if creature.behaviour == Behaviour::Wander || toward.is_none() {
+				creature.wandering = match mob.walk_state {
+					WalkState::TryLeft => WalkState::TryRight,
+					_ => WalkState::TryLeft,
+				};
+			}
+		}
+	}
+}
+
+const MOB_PUSH_STRENGTH: f32 = 20.0;
 
-		mob.position = new_loc;
-		mob.velocity = new_velocity;
+fn push_mobs(
+	time: Res<Time>,
+	mut mob_query: Query<&mut Mob>,
+) {
+	let mut pairs = mob_query.iter_combinations_mut();
+	while let Some([mut a, mut b]) = pairs.fetch_next() {
+		if !a.pushes_mobs || !b.pushes_mobs { continue; }
+		let overlap: Vec2 = (a.position + a.size).min(b.position + b.size) - a.position.max(b.position);
+		if overlap.x <= 0.0 || overlap.y <= 0.0 { continue; }
+
+		// Push sideways only, so mobs can still stand on each other's heads
+		let direction: f32 = match (a.position.x + a.size.x * 0.5) < (b.position.x + b.size.x * 0.5) {
+			true => -1.0,
+			false => 1.0,
+		};
+		let push: f32 = overlap.x * MOB_PUSH_STRENGTH * time.delta_secs() * direction;
+		a.velocity.x += push;
+		b.velocity.x -= push;
 	}
 }
 
@@ -727,7 +4575,6 @@ fn walk_animation(
 			&mut PlayerAnimationTimer,
 			&PlayerAnimation
 		),
-		With<Player>
 	>
 ) {
 	for (mob, mut transfem, mut sprite, mut timer, data) in &mut query {
@@ -735,9 +4582,31 @@ fn walk_animation(
 			WalkState::None => (),
 			_ => transfem.scale = Vec3::new(mob.walk_state as i32 as f32, 1.0, 1.0),
 		};
+		let sheet: &(Handle<Image>, Handle<TextureAtlasLayout>) = match mob.climb_state {
+			ClimbState::Climbing => &data.climb_sheet,
+			_ if mob.touching_grass && !matches!(mob.walk_state, WalkState::None) => &data.walk_sheet,
+			_ => &data.idle_sheet,
+		};
+		if sprite.image != sheet.0 {
+			sprite.image = sheet.0.clone();
+			sprite.texture_atlas = Some(TextureAtlas {
+				layout: sheet.1.clone(),
+				index: 0,
+			});
+		}
 		timer.tick(time.delta());
 		if timer.just_finished() {
-			if mob.touching_grass && !matches!(mob.walk_state, WalkState::None){
+			if mob.climb_state == ClimbState::Climbing {
+				if (sprite.texture_atlas.as_mut().unwrap().index < data.first_climb_frame) || (sprite.texture_atlas.as_mut().unwrap().index > data.last_climb_frame) {
+					sprite.texture_atlas.as_mut().unwrap().index = data.first_climb_frame;
+				} else if mob.velocity != Vec2::ZERO {
+					if sprite.texture_atlas.as_mut().unwrap().index >= data.last_climb_frame {
+						sprite.texture_atlas.as_mut().unwrap().index = data.first_climb_frame;
+					} else {
+						sprite.texture_atlas.as_mut().unwrap().index += 1;
+					}
+				}
+			} else if mob.touching_grass && !matches!(mob.walk_state, WalkState::None){
 				if (sprite.texture_atlas.as_mut().unwrap().index < data.first_walk_frame) || (sprite.texture_atlas.as_mut().unwrap().index >= data.last_walk_frame) {
 					sprite.texture_atlas.as_mut().unwrap().index = data.first_walk_frame;
 				} else {
@@ -759,12 +4628,14 @@ fn fps_update_config(input: Res<ButtonInput<KeyCode>>, mut overlay: ResMut<FpsOv
 
 fn setup (
 	mut commands: Commands,
+	physics_config: Res<PhysicsConfig>,
 	asset_server: Res<AssetServer>,
 	mut texture_atlases: ResMut<Assets<TextureAtlasLayout>>,
 	mut settings: ResMut<FramepaceSettings>,
 ) {
 	commands.spawn((
-		Camera2d::default(),
+		Camera2d,
+		SpatialListener::new(32.0),
 		Projection::from(OrthographicProjection {
 			scale: 0.5,
 			..OrthographicProjection::default_2d()
@@ -780,10 +4651,6 @@ fn setup (
 	let _: Handle<Image> = asset_server.load("UI/button-up.png");
 	let _: Handle<Image> = asset_server.load("UI/selector.png");
 
-	let _: Handle<Image> = asset_server.load("Sprites/delete.png");
-	let _: Handle<Image> = asset_server.load("Sprites/player_human.png");
-	let _: Handle<Image> = asset_server.load("Sprites/player_radlad.png");
-
 /*	let _: Handle<TextureAtlasLayout> = texture_atlases.add(
 		TextureAtlasLayout::from_grid(
 			asset_server.load("Sprites/player_walk_human.png"),
@@ -802,6 +4669,15 @@ fn setup (
 			None
 		)
 	);
+	let climb_atlas: Handle<TextureAtlasLayout> = texture_atlases.add(
+		TextureAtlasLayout::from_grid(
+			UVec2::new(16, 32),
+			2,
+			1,
+			None,
+			None
+		)
+	);
 /*	let _: Handle<TextureAtlasLayout> = texture_atlases.add(
 		TextureAtlasLayout::from_grid(
 			asset_server.load("Sprites/player_walk_radlad.png"),
@@ -812,37 +4688,75 @@ fn setup (
 			None
 	));*/
 
+	let schedule_fall_hook: TileHook = commands.register_system(schedule_fall);
+	let fall_hook: TileTickHook = commands.register_system(fall_when_unsupported);
+	let grass_hook: TileTickHook = commands.register_system(grass_tick);
 	commands.insert_resource(TileIds::new([
-		TileData::new(false, false,
+		TileData::new(false, SmoothGroups::NONE, false,
 			(texture_atlases.add(TextureAtlasLayout::from_grid(UVec2::splat(12),1,1,None,None)), asset_server.load("Sprites/Blocks/air.png"))
-		),//TileIds::AIR
-		TileData::new(true, true,
+		).with_hardness(0.0),//TileIds::AIR
+		TileData::new(true, SmoothGroups::EARTH, true,
 			(texture_atlases.add(TextureAtlasLayout::from_grid(UVec2::splat(12),4,4,None,None)), asset_server.load("Sprites/Blocks/dirt.png"))
-		),//TileIds::DIRT
-		TileData::new(true, true,
+		).with_hardness(0.4).with_sounds(TileSounds::block()),//TileIds::DIRT
+		TileData::new(true, SmoothGroups::EARTH, true,
 			(texture_atlases.add(TextureAtlasLayout::from_grid(UVec2::splat(12),4,4,None,None)), asset_server.load("Sprites/Blocks/grass.png"))
-		),//TileIds::GRASS
-		TileData::new(true, true,
+		).with_placement(PlacementRule::Supported).with_hardness(0.45).with_hooks(TileHooks {
+			on_random_tick: Some(grass_hook),
+			..default()
+		}).with_sounds(TileSounds::block()),//TileIds::GRASS
+		TileData::new(true, SmoothGroups::WOOD, true,
 			(texture_atlases.add(TextureAtlasLayout::from_grid(UVec2::splat(12),4,4,None,None)), asset_server.load("Sprites/Blocks/log.png"))
-		),//TileIds::LOG
-		TileData::new(true, true,
+		).with_hardness(0.9).with_sounds(TileSounds::block()),//TileIds::LOG
+		TileData::new(true, SmoothGroups::WOOD, true,
 			(texture_atlases.add(TextureAtlasLayout::from_grid(UVec2::splat(12),4,4,None,None)), asset_server.load("Sprites/Blocks/wood.png"))
-		),//TileIds::WOOD
-		TileData::new(true, true,
+		).with_hardness(0.75).with_sounds(TileSounds::block()),//TileIds::WOOD
+		TileData::new(true, SmoothGroups::STONE, true,
 			(texture_atlases.add(TextureAtlasLayout::from_grid(UVec2::splat(12),4,4,None,None)), asset_server.load("Sprites/Blocks/stone.png"))
-		),//TileIds::STONE
-		TileData::new(true, true,
+		).with_hardness(1.5).with_sounds(TileSounds::block()),//TileIds::STONE
+		TileData::new(true, SmoothGroups::STONE, true,
 			(texture_atlases.add(TextureAtlasLayout::from_grid(UVec2::splat(12),4,4,None,None)), asset_server.load("Sprites/Blocks/stonebrick.png"))
-		),//TileIds::STONEBRICK
-		TileData::new(true, true,
+		).with_hardness(1.8).with_sounds(TileSounds::block()),//TileIds::STONEBRICK
+		TileData::new(true, SmoothGroups::GLASS, true,
 			(texture_atlases.add(TextureAtlasLayout::from_grid(UVec2::splat(12),4,4,None,None)), asset_server.load("Sprites/Blocks/glass.png"))
-		),//TileIds::GLASS
-		TileData::new(true, true, 
+		).with_hardness(0.3).with_sounds(TileSounds::block()).with_surface(0.4, 0.0).with_light(0, 0),//TileIds::GLASS
+		TileData::new(true, SmoothGroups::GLASS, true,
 			(texture_atlases.add(TextureAtlasLayout::from_grid(UVec2::splat(12),4,4,None,None)), asset_server.load("Sprites/Blocks/glasspane.png"))
-		),//TileIds::GLASSPANE
+		).with_hardness(0.2).with_sounds(TileSounds::block()).with_surface(0.4, 0.0).with_shape(CollisionShape::Pane).with_light(0, 0),//TileIds::GLASSPANE
+		TileData::new(false, SmoothGroups::NONE, true,
+			(texture_atlases.add(TextureAtlasLayout::from_grid(UVec2::splat(12),1,1,None,None)), asset_server.load("Sprites/Blocks/platform.png"))
+		).with_hardness(0.5).with_sounds(TileSounds::block()).with_shape(CollisionShape::Platform).with_light(1, 0),//TileIds::PLATFORM
+		TileData::new(false, SmoothGroups::NONE, false,
+			(texture_atlases.add(TextureAtlasLayout::from_grid(UVec2::splat(12),1,1,None,None)), asset_server.load("Sprites/Blocks/ladder.png"))
+		).with_hardness(0.3).with_sounds(TileSounds::block()).with_climbable(),//TileIds::LADDER
+		TileData::new(false, SmoothGroups::NONE, false,
+			(texture_atlases.add(TextureAtlasLayout::from_grid(UVec2::splat(12),FluidLevels::MAX as u32,1,None,None)), asset_server.load("Sprites/Blocks/water.png"))
+		).with_placement(PlacementRule::Anywhere).with_hardness(0.0).with_fluid(FluidData { flow_interval: 4, drag: 10.0 }).with_light(1, 0),//TileIds::WATER
+		TileData::new(false, SmoothGroups::EARTH, true,
+			(texture_atlases.add(TextureAtlasLayout::from_grid(UVec2::splat(12),1,1,None,None)), asset_server.load("Sprites/Blocks/sand.png"))
+		).with_placement(PlacementRule::Anywhere).with_hardness(0.35).with_sounds(TileSounds::block()).with_falls(TileHooks {
+			on_placed: Some(schedule_fall_hook),
+			on_neighbour_changed: Some(schedule_fall_hook),
+			on_scheduled_tick: Some(fall_hook),
+			..default()
+		}),//TileIds::SAND
+		TileData::new(false, SmoothGroups::NONE, true,
+			(texture_atlases.add(TextureAtlasLayout::from_grid(UVec2::splat(12),1,1,None,None)), asset_server.load("Sprites/Blocks/lamp.png"))
+		).with_hardness(0.3).with_sounds(TileSounds::block()).with_light(4, 14),//TileIds::LAMP
+		TileData::new(false, SmoothGroups::NONE, true,
+			(texture_atlases.add(TextureAtlasLayout::from_grid(UVec2::splat(12),1,1,None,None)), asset_server.load("Sprites/Blocks/stoneslab.png"))
+		).with_hardness(1.5).with_sounds(TileSounds::block()).with_shape(CollisionShape::HalfBottom).with_light(2, 0),//TileIds::STONESLAB
+		TileData::new(false, SmoothGroups::NONE, true,
+			(texture_atlases.add(TextureAtlasLayout::from_grid(UVec2::splat(12),1,1,None,None)), asset_server.load("Sprites/Blocks/stoneslabtop.png"))
+		).with_hardness(1.5).with_sounds(TileSounds::block()).with_shape(CollisionShape::HalfTop).with_light(2, 0),//TileIds::STONESLABTOP
+		TileData::new(false, SmoothGroups::NONE, true,
+			(texture_atlases.add(TextureAtlasLayout::from_grid(UVec2::splat(12),1,1,None,None)), asset_server.load("Sprites/Blocks/stonesloperight.png"))
+		).with_hardness(1.5).with_sounds(TileSounds::block()).with_shape(CollisionShape::SlopeRight).with_light(2, 0),//TileIds::STONESLOPERIGHT
+		TileData::new(false, SmoothGroups::NONE, true,
+			(texture_atlases.add(TextureAtlasLayout::from_grid(UVec2::splat(12),1,1,None,None)), asset_server.load("Sprites/Blocks/stoneslopeleft.png"))
+		).with_hardness(1.5).with_sounds(TileSounds::block()).with_shape(CollisionShape::SlopeLeft).with_light(2, 0),//TileIds::STONESLOPELEFT
 	]));
 
-	commands.spawn((
+	let player: Entity = commands.spawn((
 		Player::default(),
 		Mob {
 			..default()
@@ -863,7 +4777,153 @@ fn setup (
 			..default()
 		},
 		PlayerAnimationTimer(Timer::from_seconds(0.1, TimerMode::Repeating)),
-		PlayerAnimation::default()
+		PlayerAnimation {
+			idle_sheet: (asset_server.load("Sprites/player_lizard.png"), lizard_walk_atlas.clone()),
+			walk_sheet: (asset_server.load("Sprites/player_lizard.png"), lizard_walk_atlas.clone()),
+			climb_sheet: (asset_server.load("Sprites/player_climb_lizard.png"), climb_atlas.clone()),
+			..default()
+		},
+		Footsteps::default(),
+	)).id();
+	// Read once at spawn, later edits to the global config don't reach an overridden player
+	if let Ok(physics) = MobPhysics::load("player", &physics_config) {
+		commands.entity(player).insert(physics);
+	}
+
+	let creature_idle_atlas: Handle<TextureAtlasLayout> = texture_atlases.add(
+		TextureAtlasLayout::from_grid(
+			UVec2::new(16, 32),
+			1,
+			1,
+			None,
+			None
+		)
+	);
+	let creature_walk_atlas: Handle<TextureAtlasLayout> = texture_atlases.add(
+		TextureAtlasLayout::from_grid(
+			UVec2::new(16, 32),
+			8,
+			1,
+			None,
+			None
+		)
+	);
+	commands.insert_resource(CreatureSpawner {
+		rng: XorShift::new(0xD1B54A32D192ED03),
+		timer: Timer::from_seconds(5.0, TimerMode::Repeating),
+		kinds: [
+			(PlayerAnimation {
+				first_walk_frame: 0,
+				idle_sheet: (asset_server.load("Sprites/player_human.png"), creature_idle_atlas.clone()),
+				walk_sheet: (asset_server.load("Sprites/player_walk_human.png"), creature_walk_atlas.clone()),
+				climb_sheet: (asset_server.load("Sprites/player_climb_human.png"), climb_atlas.clone()),
+				..default()
+			}, Behaviour::Flee),
+			(PlayerAnimation {
+				first_walk_frame: 0,
+				idle_sheet: (asset_server.load("Sprites/player_radlad.png"), creature_idle_atlas.clone()),
+				walk_sheet: (asset_server.load("Sprites/player_walk_radlad.png"), creature_walk_atlas.clone()),
+				climb_sheet: (asset_server.load("Sprites/player_climb_radlad.png"), climb_atlas.clone()),
+				..default()
+			}, Behaviour::Follow),
+		],
+	});
+
+	commands.insert_resource(SoundRegistry::new([
+		(Some(asset_server.load("deconstruct.ogg")), SoundCategory::Tiles),//SoundRegistry::DECONSTRUCT
+		(None, SoundCategory::Tiles),//SoundRegistry::PLACE
+		(None, SoundCategory::Footsteps),//SoundRegistry::STEP
+	]));
+
+	let particles: Vec<Entity> = (0..ParticlePool::CAPACITY).map(|_| commands.spawn((
+		Particle::default(),
+		Sprite::default(),
+		Transform {
+			translation: Vec3 {
+				z: OVERLAY_LAYER,
+				..default()
+			},
+			..default()
+		},
+		Visibility::Hidden,
+	)).id()).collect();
+	commands.insert_resource(ParticlePool {
+		free: particles,
+		rng: XorShift::new(0x9E3779B97F4A7C15),
+	});
+
+	commands.spawn((
+		BreakOverlay,
+		Sprite {
+			image: asset_server.load("Sprites/delete.png"),
+			..default()
+		},
+		Transform {
+			translation: Vec3 {
+				z: OVERLAY_LAYER,
+				..default()
+			},
+			..default()
+		},
+		Visibility::Hidden,
+	));
+
+	commands.spawn((
+		Text::new(""),
+		TextFont {
+			font: asset_server.load("UI/small_font.ttf"),
+			font_size: 16.0,
+			..default()
+		},
+		TextColor(Color::srgb(1.0, 0.3, 0.3)),
+		Node {
+			position_type: PositionType::Absolute,
+			bottom: Val::Px(8.0),
+			left: Val::Px(8.0),
+			..default()
+		},
+		Visibility::Hidden,
+		RejectionText {
+			timer: Timer::from_seconds(1.5, TimerMode::Once),
+		},
+	));
+
+	commands.spawn((
+		Text::new(""),
+		TextFont {
+			font: asset_server.load("UI/small_font.ttf"),
+			font_size: 16.0,
+			..default()
+		},
+		Node {
+			position_type: PositionType::Absolute,
+			bottom: Val::Px(28.0),
+			left: Val::Px(8.0),
+			..default()
+		},
+		Visibility::Hidden,
+		SchematicPrompt {
+			timer: Timer::from_seconds(2.0, TimerMode::Once),
+		},
+	));
+
+	commands.spawn((
+		Text::new(""),
+		TextFont {
+			font: asset_server.load("UI/small_font.ttf"),
+			font_size: 16.0,
+			..default()
+		},
+		Node {
+			position_type: PositionType::Absolute,
+			top: Val::Px(8.0),
+			right: Val::Px(8.0),
+			..default()
+		},
+		Visibility::Hidden,
+		PhysicsPanel {
+			selected: 0,
+		},
 	));
 }
 